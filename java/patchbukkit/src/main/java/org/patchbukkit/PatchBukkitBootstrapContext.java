@@ -0,0 +1,73 @@
+package org.patchbukkit.loader;
+
+import io.papermc.paper.plugin.bootstrap.BootstrapContext;
+import io.papermc.paper.plugin.configuration.PluginMeta;
+import io.papermc.paper.plugin.lifecycle.event.LifecycleEventManager;
+import io.papermc.paper.plugin.lifecycle.event.handler.configuration.LifecycleEventHandlerConfiguration;
+import java.io.File;
+import java.nio.file.Path;
+import java.util.ArrayList;
+import java.util.List;
+import net.kyori.adventure.text.logger.slf4j.ComponentLogger;
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * The context handed to a Paper plugin's {@code PluginLoader} and {@code PluginBootstrap}.
+ */
+public class PatchBukkitBootstrapContext implements BootstrapContext {
+
+    private final PluginMeta configuration;
+    private final File dataFolder;
+    private final File jarFile;
+    private final ComponentLogger logger;
+    private final BootstrapLifecycleManager lifecycleManager = new BootstrapLifecycleManager();
+
+    public PatchBukkitBootstrapContext(PluginMeta configuration, File dataFolder, File jarFile) {
+        this.configuration = configuration;
+        this.dataFolder = dataFolder;
+        this.jarFile = jarFile;
+        this.logger = ComponentLogger.logger(configuration.getName());
+    }
+
+    @Override
+    public @NotNull PluginMeta getConfiguration() {
+        return configuration;
+    }
+
+    @Override
+    public @NotNull Path getDataDirectory() {
+        return dataFolder.toPath();
+    }
+
+    @Override
+    public @NotNull ComponentLogger getLogger() {
+        return logger;
+    }
+
+    @Override
+    public @NotNull Path getPluginSource() {
+        return jarFile.toPath();
+    }
+
+    @Override
+    public @NotNull LifecycleEventManager<BootstrapContext> getLifecycleManager() {
+        return lifecycleManager;
+    }
+
+    /**
+     * Keeps the handlers registered while bootstrapping. Pumpkin has no registry
+     * lifecycle, so they are recorded but never fired.
+     */
+    private final class BootstrapLifecycleManager implements LifecycleEventManager<BootstrapContext> {
+
+        private final List<LifecycleEventHandlerConfiguration<? super BootstrapContext>> handlers = new ArrayList<>();
+
+        @Override
+        public void registerEventHandler(
+            @NotNull LifecycleEventHandlerConfiguration<? super BootstrapContext> handlerConfiguration
+        ) {
+            handlers.add(handlerConfiguration);
+            logger.warn("Lifecycle events are not supported by PatchBukkit, the handler will never be called");
+        }
+    }
+}
@@ -0,0 +1,49 @@
+package org.patchbukkit.loader;
+
+import io.papermc.paper.plugin.bootstrap.PluginProviderContext;
+import io.papermc.paper.plugin.loader.PluginClasspathBuilder;
+import io.papermc.paper.plugin.loader.library.ClassPathLibrary;
+import io.papermc.paper.plugin.loader.library.LibraryLoadingException;
+import java.net.MalformedURLException;
+import java.net.URL;
+import java.util.LinkedHashSet;
+import java.util.Set;
+import org.jetbrains.annotations.NotNull;
+
+/**
+ * Collects the libraries a Paper plugin's {@code PluginLoader} adds to its classpath.
+ */
+public class PatchBukkitClasspathBuilder implements PluginClasspathBuilder {
+
+    private final PluginProviderContext context;
+    private final Set<URL> libraries = new LinkedHashSet<>();
+
+    public PatchBukkitClasspathBuilder(PluginProviderContext context) {
+        this.context = context;
+    }
+
+    @Override
+    public @NotNull PluginClasspathBuilder addLibrary(@NotNull ClassPathLibrary classPathLibrary) {
+        try {
+            classPathLibrary.register(library -> {
+                try {
+                    libraries.add(library.toUri().toURL());
+                } catch (MalformedURLException e) {
+                    throw new LibraryLoadingException("Invalid library path " + library, e);
+                }
+            });
+        } catch (LibraryLoadingException e) {
+            context.getLogger().error("Failed to load library " + classPathLibrary, e);
+        }
+        return this;
+    }
+
+    @Override
+    public @NotNull PluginProviderContext getContext() {
+        return context;
+    }
+
+    public Set<URL> getLibraries() {
+        return libraries;
+    }
+}
@@ -41,11 +41,22 @@ public class PatchBukkitPluginClassLoader
         ClassLoader parent,
         File file,
         URL[] extraUrls
+    ) throws MalformedURLException, InvalidDescriptionException {
+        this(parent, file, null, extraUrls);
+    }
+
+    public PatchBukkitPluginClassLoader(
+        ClassLoader parent,
+        File file,
+        @Nullable File dataFolder,
+        URL[] extraUrls
     ) throws MalformedURLException, InvalidDescriptionException {
         super(buildUrls(file, extraUrls), parent);
         this.file = file;
         this.description = loadDescription(file);
-        this.dataFolder = new File(file.getParentFile(), description.getName());
+        this.dataFolder = dataFolder != null
+            ? dataFolder
+            : new File(file.getParentFile(), description.getName());
     }
 
     private static URL[] buildUrls(File file, URL[] extraUrls)
@@ -177,6 +188,13 @@ public class PatchBukkitPluginClassLoader
         return dataFolder;
     }
 
+    /**
+     * Adds a library contributed by the plugin's Paper {@code PluginLoader}.
+     */
+    public void addLibrary(URL library) {
+        addURL(library);
+    }
+
     @Override
     public void close() throws IOException {
         super.close();
@@ -1,13 +1,18 @@
 package org.patchbukkit.loader;
 
 import java.io.File;
+import java.io.IOException;
 import java.net.URL;
 import java.util.LinkedHashSet;
 import java.util.List;
 import java.util.Map;
 import java.util.Set;
+import java.util.concurrent.ConcurrentHashMap;
 import java.util.regex.Pattern;
+import io.papermc.paper.plugin.bootstrap.PluginBootstrap;
+import org.bukkit.Bukkit;
 import org.bukkit.event.Event;
+import org.bukkit.event.HandlerList;
 import org.bukkit.event.Listener;
 import org.bukkit.plugin.EventExecutor;
 import org.bukkit.plugin.InvalidDescriptionException;
@@ -18,68 +23,205 @@ import org.bukkit.plugin.PluginLoader;
 import org.bukkit.plugin.RegisteredListener;
 import org.bukkit.plugin.UnknownDependencyException;
 import org.bukkit.plugin.java.JavaPlugin;
+import org.jetbrains.annotations.Nullable;
+import org.patchbukkit.command.PatchBukkitCommandMap;
 
 @SuppressWarnings({ "deprecation", "removal" })
 public class PatchBukkitPluginLoader implements PluginLoader {
 
-    public static JavaPlugin createPlugin(
+    /**
+     * Class loaders of plugins bootstrapped ahead of their instantiation, keyed by jar path,
+     * until {@link #createPlugin} or {@link #discardBootstrap} takes them
+     */
+    private static final Map<String, BootstrappedPlugin> BOOTSTRAPPED = new ConcurrentHashMap<>();
+
+    private record BootstrappedPlugin(
+        PatchBukkitPluginClassLoader classLoader,
+        @Nullable PluginBootstrap bootstrapper,
+        PatchBukkitBootstrapContext context
+    ) {}
+
+    /**
+     * Runs the bootstrap phase of a Paper plugin. Its {@code PluginLoader} adds libraries to
+     * the plugin's class loader, then its {@code PluginBootstrap} is invoked. The class loader
+     * is kept for {@link #createPlugin}.
+     *
+     * @param bootstrapperClass The {@code bootstrapper} from paper-plugin.yml, or an empty string
+     * @param loaderClass The {@code loader} from paper-plugin.yml, or an empty string
+     */
+    public static void bootstrapPlugin(
         String jarPath,
-        String mainClass,
+        String bootstrapperClass,
+        String loaderClass,
+        String dataFolderPath,
         String extraClasspath,
         String libraryCoordinates
-    ) {
+    ) throws Exception {
+        PatchBukkitPluginClassLoader classLoader = createClassLoader(
+            jarPath,
+            dataFolderPath,
+            extraClasspath,
+            libraryCoordinates
+        );
+        if (classLoader == null) {
+            throw new IOException("Plugin file does not exist: " + jarPath);
+        }
+
         try {
-            File jarFile = new File(jarPath);
-            if (!jarFile.exists()) {
-                System.err.println(
-                    "[PatchBukkit] Plugin file does not exist: " + jarPath
-                );
-                return null;
-            }
+            PatchBukkitBootstrapContext context = new PatchBukkitBootstrapContext(
+                classLoader.getConfiguration(),
+                classLoader.getDataFolder(),
+                new File(jarPath)
+            );
 
-            LinkedHashSet<URL> extraUrls = new LinkedHashSet<>();
-            if (extraClasspath != null && !extraClasspath.isBlank()) {
-                String[] paths = extraClasspath.split(File.pathSeparator);
-                for (String path : paths) {
-                    if (path == null || path.isBlank()) {
-                        continue;
-                    }
-                    File extraFile = new File(path.trim());
-                    if (extraFile.exists()) {
-                        extraUrls.add(extraFile.toURI().toURL());
-                    }
+            if (!loaderClass.isBlank()) {
+                io.papermc.paper.plugin.loader.PluginLoader loader =
+                    (io.papermc.paper.plugin.loader.PluginLoader) Class
+                        .forName(loaderClass, true, classLoader)
+                        .getDeclaredConstructor()
+                        .newInstance();
+                PatchBukkitClasspathBuilder classpathBuilder = new PatchBukkitClasspathBuilder(context);
+                loader.classloader(classpathBuilder);
+                for (URL library : classpathBuilder.getLibraries()) {
+                    classLoader.addLibrary(library);
                 }
             }
 
-            if (libraryCoordinates != null && !libraryCoordinates.isBlank()) {
-                File libsDir = new File(jarFile.getParentFile(), "patchbukkit-libs");
-                if (!libsDir.exists()) {
-                    libsDir.mkdirs();
-                }
-                List<File> libraries = LibraryResolver.resolveLibraries(
-                    libraryCoordinates,
-                    libsDir
-                );
-                for (File lib : libraries) {
-                    if (lib != null && lib.exists()) {
-                        extraUrls.add(lib.toURI().toURL());
+            PluginBootstrap bootstrapper = null;
+            if (!bootstrapperClass.isBlank()) {
+                bootstrapper = (PluginBootstrap) Class
+                    .forName(bootstrapperClass, true, classLoader)
+                    .getDeclaredConstructor()
+                    .newInstance();
+                bootstrapper.bootstrap(context);
+            }
+
+            BOOTSTRAPPED.put(jarPath, new BootstrappedPlugin(classLoader, bootstrapper, context));
+        } catch (Exception e) {
+            classLoader.close();
+            throw e;
+        }
+    }
+
+    public static JavaPlugin createPlugin(
+        String jarPath,
+        String mainClass,
+        String dataFolderPath,
+        String extraClasspath,
+        String libraryCoordinates
+    ) throws Exception {
+        BootstrappedPlugin bootstrapped = BOOTSTRAPPED.remove(jarPath);
+        PatchBukkitPluginClassLoader classLoader = bootstrapped != null
+            ? bootstrapped.classLoader()
+            : createClassLoader(jarPath, dataFolderPath, extraClasspath, libraryCoordinates);
+        if (classLoader == null) {
+            throw new IOException("Plugin file does not exist: " + jarPath);
+        }
+
+        try {
+            if (bootstrapped != null && bootstrapped.bootstrapper() != null) {
+                JavaPlugin plugin = bootstrapped.bootstrapper().createPlugin(bootstrapped.context());
+                if (plugin != null) {
+                    // unloadPlugin only closes the class loader the plugin class comes from
+                    if (plugin.getClass().getClassLoader() != classLoader) {
+                        classLoader.close();
                     }
+                    return plugin;
                 }
             }
 
-            PatchBukkitPluginClassLoader classLoader =
-                new PatchBukkitPluginClassLoader(
-                    PatchBukkitPluginLoader.class.getClassLoader(),
-                    jarFile,
-                    extraUrls.toArray(new URL[0])
-                );
-
             Class<?> jarClass = Class.forName(mainClass, true, classLoader);
             return (JavaPlugin) jarClass.getDeclaredConstructor().newInstance();
         } catch (Exception e) {
-            e.printStackTrace();
+            classLoader.close();
+            throw e;
+        }
+    }
+
+    /**
+     * Closes the class loader kept for a bootstrapped plugin that will not be instantiated,
+     * e.g. because a dependency failed.
+     *
+     * @param jarPath The jar path given to {@link #bootstrapPlugin}
+     * @throws IOException If the class loader could not be closed
+     */
+    public static void discardBootstrap(String jarPath) throws IOException {
+        BootstrappedPlugin bootstrapped = BOOTSTRAPPED.remove(jarPath);
+        if (bootstrapped != null) {
+            bootstrapped.classLoader().close();
+        }
+    }
+
+    private static @Nullable PatchBukkitPluginClassLoader createClassLoader(
+        String jarPath,
+        String dataFolderPath,
+        String extraClasspath,
+        String libraryCoordinates
+    ) throws Exception {
+        File jarFile = new File(jarPath);
+        if (!jarFile.exists()) {
             return null;
         }
+
+        LinkedHashSet<URL> extraUrls = new LinkedHashSet<>();
+        if (extraClasspath != null && !extraClasspath.isBlank()) {
+            String[] paths = extraClasspath.split(File.pathSeparator);
+            for (String path : paths) {
+                if (path == null || path.isBlank()) {
+                    continue;
+                }
+                File extraFile = new File(path.trim());
+                if (extraFile.exists()) {
+                    extraUrls.add(extraFile.toURI().toURL());
+                }
+            }
+        }
+
+        if (libraryCoordinates != null && !libraryCoordinates.isBlank()) {
+            File libsDir = new File(jarFile.getParentFile(), "patchbukkit-libs");
+            if (!libsDir.exists()) {
+                libsDir.mkdirs();
+            }
+            List<File> libraries = LibraryResolver.resolveLibraries(
+                libraryCoordinates,
+                libsDir
+            );
+            for (File lib : libraries) {
+                if (lib != null && lib.exists()) {
+                    extraUrls.add(lib.toURI().toURL());
+                }
+            }
+        }
+
+        File dataFolder = null;
+        if (dataFolderPath != null && !dataFolderPath.isBlank()) {
+            dataFolder = new File(dataFolderPath);
+        }
+
+        return new PatchBukkitPluginClassLoader(
+            PatchBukkitPluginLoader.class.getClassLoader(),
+            jarFile,
+            dataFolder,
+            extraUrls.toArray(new URL[0])
+        );
+    }
+
+    /**
+     * Releases a disabled plugin so its jar can be replaced and loaded again.
+     *
+     * @param plugin The plugin created by {@link #createPlugin}
+     * @throws IOException If the plugin's class loader could not be closed
+     */
+    public static void unloadPlugin(JavaPlugin plugin) throws IOException {
+        if (plugin.isEnabled()) {
+            throw new IllegalStateException(
+                "Plugin " + plugin.getName() + " must be disabled before it is unloaded"
+            );
+        }
+
+        if (plugin.getClass().getClassLoader() instanceof PatchBukkitPluginClassLoader classLoader) {
+            classLoader.close();
+        }
     }
 
     @Override
@@ -113,6 +255,12 @@ public class PatchBukkitPluginLoader implements PluginLoader {
         if (plugin instanceof JavaPlugin javaPlugin) {
             javaPlugin.setEnabled(false);
         }
+
+        // Rust re-registers commands and listeners if the plugin is enabled again
+        HandlerList.unregisterAll(plugin);
+        if (Bukkit.getCommandMap() instanceof PatchBukkitCommandMap commandMap) {
+            commandMap.unregisterPluginCommands(plugin);
+        }
     }
 
     @Override
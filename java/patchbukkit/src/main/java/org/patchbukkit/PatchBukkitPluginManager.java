@@ -8,6 +8,7 @@ import java.util.List;
 import java.util.Map;
 import java.util.Set;
 import java.util.concurrent.ConcurrentHashMap;
+import java.util.logging.Level;
 
 import org.bukkit.Server;
 import org.bukkit.event.Event;
@@ -156,7 +157,9 @@ public class PatchBukkitPluginManager implements PluginManager {
             try {
                 plugin.getPluginLoader().enablePlugin(plugin);
             } catch (Throwable ex) {
-                server.getLogger().severe("Error enabling " + plugin.getName() + ": " + ex.getMessage());
+                server.getLogger().log(Level.SEVERE, "Error enabling " + plugin.getName(), ex);
+                // Like Paper, a plugin that failed to enable is disabled again
+                disablePlugin(plugin);
             }
         }
     }
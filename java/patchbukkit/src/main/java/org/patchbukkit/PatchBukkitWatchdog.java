@@ -0,0 +1,64 @@
+package org.patchbukkit;
+
+import java.util.Map;
+
+/**
+ * Java side of the PatchBukkit watchdog, called from the Rust watchdog thread when the
+ * JVM worker has been busy with one command for too long.
+ */
+public final class PatchBukkitWatchdog {
+
+    private static volatile Thread workerThread;
+
+    private PatchBukkitWatchdog() {}
+
+    /** Remembers the calling thread as the JVM worker, so it is dumped first. */
+    public static void watchCurrentThread() {
+        workerThread = Thread.currentThread();
+    }
+
+    /** Stack traces of every live thread, the JVM worker first. */
+    public static String dumpThreads() {
+        Thread worker = workerThread;
+        StringBuilder dump = new StringBuilder();
+        if (worker != null) {
+            appendThread(dump, worker, worker.getStackTrace());
+        }
+        for (Map.Entry<Thread, StackTraceElement[]> entry : Thread.getAllStackTraces().entrySet()) {
+            if (entry.getKey() != worker) {
+                appendThread(dump, entry.getKey(), entry.getValue());
+            }
+        }
+        return dump.toString();
+    }
+
+    /**
+     * Interrupts the JVM worker, which frees it when the plugin is sleeping, waiting or
+     * doing interruptible I/O. A busy loop is not affected.
+     */
+    public static boolean interruptWorker() {
+        Thread worker = workerThread;
+        if (worker == null) {
+            return false;
+        }
+        worker.interrupt();
+        return true;
+    }
+
+    private static void appendThread(
+        StringBuilder dump,
+        Thread thread,
+        StackTraceElement[] stackTrace
+    ) {
+        dump
+            .append("Thread \"")
+            .append(thread.getName())
+            .append("\" ")
+            .append(thread.getState())
+            .append(thread == workerThread ? " (PatchBukkit JVM worker)" : "")
+            .append('\n');
+        for (StackTraceElement element : stackTrace) {
+            dump.append("    at ").append(element).append('\n');
+        }
+    }
+}
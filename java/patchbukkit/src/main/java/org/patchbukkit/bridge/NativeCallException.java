@@ -0,0 +1,25 @@
+package org.patchbukkit.bridge;
+
+/**
+ * Thrown when a native PatchBukkit callback reports a failure instead of a result.
+ */
+public class NativeCallException extends RuntimeException {
+
+    private final int status;
+
+    public NativeCallException(String callback, int status, String message) {
+        super(
+            callback +
+            " failed (" +
+            NativePatchBukkit.statusName(status) +
+            "): " +
+            (message != null ? message : "no details")
+        );
+        this.status = status;
+    }
+
+    /** The status code returned by the callback, one of the {@code STATUS_*} constants. */
+    public int getStatus() {
+        return status;
+    }
+}
@@ -14,6 +14,13 @@ public class NativePatchBukkit {
 
     private static final Linker LINKER = Linker.nativeLinker();
 
+    // Status codes returned by every native callback, matching status.rs
+    public static final int STATUS_OK = 0;
+    public static final int STATUS_NOT_FOUND = 1;
+    public static final int STATUS_INVALID_ARGUMENT = 2;
+    public static final int STATUS_NOT_INITIALIZED = 3;
+    public static final int STATUS_PANIC = 4;
+
     private static MethodHandle sendMessageNative;
     private static MethodHandle registerEventNative;
     private static MethodHandle callEventNative;
@@ -25,6 +32,7 @@ public class NativePatchBukkit {
     private static MethodHandle getRegistryDataNative;
     private static MethodHandle playerEntityPlaySoundNative;
     private static MethodHandle playerPlaySoundNative;
+    private static MethodHandle lastErrorNative;
 
     // Struct layout matching Rust's #[repr(C)] AbilitiesFFI
     private static final StructLayout ABILITIES_LAYOUT =
@@ -195,18 +203,22 @@ public class NativePatchBukkit {
         long getWorldAddr,
         long getRegistryDataAddr,
         long playerEntityPlaySoundAddr,
-        long playerPlaySoundAddr
+        long playerPlaySoundAddr,
+        long lastErrorAddr
     ) {
-        // void rust_send_message(const char* uuid, const char* message)
+        // Every callback returns an int32_t status, see check()
+
+        // int32_t rust_send_message(const char* uuid, const char* message)
         sendMessageNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(sendMessageAddr),
-            FunctionDescriptor.ofVoid(ValueLayout.ADDRESS, ValueLayout.ADDRESS)
+            FunctionDescriptor.of(ValueLayout.JAVA_INT, ValueLayout.ADDRESS, ValueLayout.ADDRESS)
         );
 
-        // void rust_register_event(const char* event_type, const char* plugin_name, int32_t priority, bool blocking)
+        // int32_t rust_register_event(const char* event_type, const char* plugin_name, int32_t priority, bool blocking)
         registerEventNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(registerEventAddr),
-            FunctionDescriptor.ofVoid(
+            FunctionDescriptor.of(
+                ValueLayout.JAVA_INT,     // status
                 ValueLayout.ADDRESS,      // event_type string
                 ValueLayout.ADDRESS,      // plugin_name string
                 ValueLayout.JAVA_INT,     // priority ordinal
@@ -214,70 +226,79 @@ public class NativePatchBukkit {
             )
         );
 
-        // bool rust_call_event(const char* event_type, const char* event_data_json)
-        // Returns true if Pumpkin handled it, false if unknown event type
+        // int32_t rust_call_event(const char* event_type, const char* event_data_json)
+        // Returns STATUS_NOT_FOUND if Pumpkin did not handle it, e.g. an unknown event type
         callEventNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(callEventAddr),
             FunctionDescriptor.of(
-                ValueLayout.JAVA_BOOLEAN, // return: was handled by Pumpkin
+                ValueLayout.JAVA_INT,     // status
                 ValueLayout.ADDRESS,      // event_type string
                 ValueLayout.ADDRESS       // event_data_json string
             )
         );
 
-        // bool rust_get_abilities(const char* uuid, AbilitiesFFI* out)
+        // int32_t rust_get_abilities(const char* uuid, AbilitiesFFI* out)
         getAbilitiesNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(getAbilitiesAddr),
             FunctionDescriptor.of(
-                ValueLayout.JAVA_BOOLEAN, // return type
+                ValueLayout.JAVA_INT, // status
                 ValueLayout.ADDRESS, // uuid string
                 ValueLayout.ADDRESS // out pointer to AbilitiesFFI
             )
         );
 
-        // bool rust_set_abilities(const char* uuid, AbilitiesFFI* abilities)
+        // int32_t rust_set_abilities(const char* uuid, AbilitiesFFI* abilities)
         setAbilitiesNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(setAbilitiesAddr),
             FunctionDescriptor.of(
-                ValueLayout.JAVA_BOOLEAN, // return type
+                ValueLayout.JAVA_INT, // status
                 ValueLayout.ADDRESS, // uuid string
                 ValueLayout.ADDRESS // pointer to AbilitiesFFI
             )
         );
 
-        // bool rust_get_location(const char* uuid, Vec3FFI* out)
+        // int32_t rust_get_location(const char* uuid, Vec3FFI* out)
         getLocationNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(getLocationAddr),
             FunctionDescriptor.of(
-                ValueLayout.JAVA_BOOLEAN, // return type
+                ValueLayout.JAVA_INT, // status
                 ValueLayout.ADDRESS, // uuid string
                 ValueLayout.ADDRESS // out pointer to Vec3FFI
             )
         );
 
-        // const char* rust_get_world(const char* uuid)
+        // int32_t rust_get_world(const char* uuid, char** out)
         getWorldNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(getWorldAddr),
-            FunctionDescriptor.of(ValueLayout.ADDRESS, ValueLayout.ADDRESS)
+            FunctionDescriptor.of(
+                ValueLayout.JAVA_INT, // status
+                ValueLayout.ADDRESS, // uuid string
+                ValueLayout.ADDRESS // out pointer to the world UUID string
+            )
         );
 
-        // void rust_free_string(const char* str)
+        // int32_t rust_free_string(char* str)
         freeStringNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(freeStringAddr),
-            FunctionDescriptor.ofVoid(ValueLayout.ADDRESS)
+            FunctionDescriptor.of(ValueLayout.JAVA_INT, ValueLayout.ADDRESS)
         );
 
-        // const char* rust_get_registry_entries(const char* registry_name)
+        // int32_t rust_get_registry_data(const char* registry_name, char** out)
         getRegistryDataNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(getRegistryDataAddr),
-            FunctionDescriptor.of(ValueLayout.ADDRESS, ValueLayout.ADDRESS)
+            FunctionDescriptor.of(
+                ValueLayout.JAVA_INT, // status
+                ValueLayout.ADDRESS, // registry_name string
+                ValueLayout.ADDRESS // out pointer to the JSON string
+            )
         );
 
-        // void rust_player_entity_play_sound(const char* player_uuid, const char* sound_name,
+        // int32_t rust_player_entity_play_sound(const char* player_uuid, const char* sound_name,
         //     const char* sound_category, const char* entity_uuid, float volume, float pitch)
         playerEntityPlaySoundNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(playerEntityPlaySoundAddr),
-            FunctionDescriptor.ofVoid(
+            FunctionDescriptor.of(
+                ValueLayout.JAVA_INT,   // status
                 ValueLayout.ADDRESS,    // player_uuid
                 ValueLayout.ADDRESS,    // sound_name
                 ValueLayout.ADDRESS,    // sound_category
@@ -287,11 +308,12 @@ public class NativePatchBukkit {
             )
         );
 
-        // void rust_player_play_sound(const char* player_uuid, const char* sound_name,
+        // int32_t rust_player_play_sound(const char* player_uuid, const char* sound_name,
         //     const char* sound_category, double x, double y, double z, float volume, float pitch)
         playerPlaySoundNative = LINKER.downcallHandle(
             MemorySegment.ofAddress(playerPlaySoundAddr),
-            FunctionDescriptor.ofVoid(
+            FunctionDescriptor.of(
+                ValueLayout.JAVA_INT,    // status
                 ValueLayout.ADDRESS,     // player_uuid
                 ValueLayout.ADDRESS,     // sound_name
                 ValueLayout.ADDRESS,     // sound_category
@@ -302,6 +324,64 @@ public class NativePatchBukkit {
                 ValueLayout.JAVA_FLOAT   // pitch
             )
         );
+
+        // char* rust_last_error(), the message of the last failed callback on this thread
+        lastErrorNative = LINKER.downcallHandle(
+            MemorySegment.ofAddress(lastErrorAddr),
+            FunctionDescriptor.of(ValueLayout.ADDRESS)
+        );
+    }
+
+    public static String statusName(int status) {
+        return switch (status) {
+            case STATUS_OK -> "ok";
+            case STATUS_NOT_FOUND -> "not found";
+            case STATUS_INVALID_ARGUMENT -> "invalid argument";
+            case STATUS_NOT_INITIALIZED -> "not initialized";
+            case STATUS_PANIC -> "panic";
+            default -> "status " + status;
+        };
+    }
+
+    /**
+     * Turns the status returned by a callback into a result.
+     *
+     * @return true on success, false when the callback found nothing to act on
+     * @throws NativeCallException for any other status, with the message Rust recorded
+     */
+    private static boolean check(String callback, int status) throws Throwable {
+        if (status == STATUS_OK) {
+            return true;
+        }
+        if (status == STATUS_NOT_FOUND) {
+            return false;
+        }
+
+        String message = null;
+        MemorySegment messagePtr = (MemorySegment) lastErrorNative.invokeExact();
+        if (!messagePtr.equals(MemorySegment.NULL)) {
+            try {
+                message = messagePtr.reinterpret(Long.MAX_VALUE).getString(0);
+            } finally {
+                freeRustString(messagePtr);
+            }
+        }
+        throw new NativeCallException(callback, status, message);
+    }
+
+    /**
+     * Reads and frees a string Rust wrote to an out pointer.
+     */
+    private static String takeRustString(MemorySegment out) {
+        MemorySegment resultPtr = out.get(ValueLayout.ADDRESS, 0);
+        if (resultPtr.equals(MemorySegment.NULL)) {
+            return null;
+        }
+        try {
+            return resultPtr.reinterpret(Long.MAX_VALUE).getString(0);
+        } finally {
+            freeRustString(resultPtr);
+        }
     }
 
     /**
@@ -311,7 +391,9 @@ public class NativePatchBukkit {
         try (Arena arena = Arena.ofConfined()) {
             MemorySegment uuidStr = arena.allocateFrom(uuid.toString());
             MemorySegment msgStr = arena.allocateFrom(message);
-            sendMessageNative.invokeExact(uuidStr, msgStr);
+            check("rust_send_message", (int) sendMessageNative.invokeExact(uuidStr, msgStr));
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native sendMessage", t);
         }
@@ -333,7 +415,12 @@ public class NativePatchBukkit {
         try (Arena arena = Arena.ofConfined()) {
             MemorySegment eventTypeStr = arena.allocateFrom(eventType);
             MemorySegment pluginNameStr = arena.allocateFrom(pluginName);
-            registerEventNative.invokeExact(eventTypeStr, pluginNameStr, priority, blocking);
+            check(
+                "rust_register_event",
+                (int) registerEventNative.invokeExact(eventTypeStr, pluginNameStr, priority, blocking)
+            );
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to register event: " + eventType + " for plugin " + pluginName, t);
         }
@@ -350,12 +437,12 @@ public class NativePatchBukkit {
             MemorySegment uuidStr = arena.allocateFrom(uuid.toString());
             MemorySegment outStruct = arena.allocate(ABILITIES_LAYOUT);
 
-            boolean success = (boolean) getAbilitiesNative.invokeExact(
+            int status = (int) getAbilitiesNative.invokeExact(
                 uuidStr,
                 outStruct
             );
 
-            if (!success) {
+            if (!check("rust_get_abilities", status)) {
                 return null;
             }
 
@@ -368,6 +455,8 @@ public class NativePatchBukkit {
                 (float) FLY_SPEED.get(outStruct, 0L),
                 (float) WALK_SPEED.get(outStruct, 0L)
             );
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native getAbilities", t);
         }
@@ -391,10 +480,13 @@ public class NativePatchBukkit {
             FLY_SPEED.set(abilitiesStruct, 0L, abilities.flySpeed());
             WALK_SPEED.set(abilitiesStruct, 0L, abilities.walkSpeed());
 
-            return (boolean) setAbilitiesNative.invokeExact(
+            int status = (int) setAbilitiesNative.invokeExact(
                 uuidStr,
                 abilitiesStruct
             );
+            return check("rust_set_abilities", status);
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native setAbilities", t);
         }
@@ -411,12 +503,12 @@ public class NativePatchBukkit {
             MemorySegment uuidStr = arena.allocateFrom(uuid.toString());
             MemorySegment outStruct = arena.allocate(VEC3_LAYOUT);
 
-            boolean success = (boolean) getLocationNative.invokeExact(
+            int status = (int) getLocationNative.invokeExact(
                 uuidStr,
                 outStruct
             );
 
-            if (!success) {
+            if (!check("rust_get_location", status)) {
                 return null;
             }
 
@@ -425,6 +517,8 @@ public class NativePatchBukkit {
                 (double) VEC3_Y.get(outStruct, 0L),
                 (double) VEC3_Z.get(outStruct, 0L)
             );
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native getLocation", t);
         }
@@ -435,7 +529,12 @@ public class NativePatchBukkit {
      */
     private static void freeRustString(MemorySegment ptr) {
         try {
-            freeStringNative.invokeExact(ptr);
+            int status = (int) freeStringNative.invokeExact(ptr);
+            if (status != STATUS_OK) {
+                throw new NativeCallException("rust_free_string", status, null);
+            }
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to free Rust string", t);
         }
@@ -447,19 +546,17 @@ public class NativePatchBukkit {
     public static String getWorld(UUID entityUuid) {
         try (Arena arena = Arena.ofConfined()) {
             MemorySegment uuidStr = arena.allocateFrom(entityUuid.toString());
-            MemorySegment resultPtr = (MemorySegment) getWorldNative.invokeExact(uuidStr);
+            MemorySegment out = arena.allocate(ValueLayout.ADDRESS);
 
-            if (resultPtr.equals(MemorySegment.NULL)) {
+            int status = (int) getWorldNative.invokeExact(uuidStr, out);
+            if (!check("rust_get_world", status)) {
                 return null;
             }
 
             // Read the string, then free the Rust-allocated memory
-            try {
-                String result = resultPtr.reinterpret(Long.MAX_VALUE).getString(0);
-                return result;
-            } finally {
-                freeRustString(resultPtr);
-            }
+            return takeRustString(out);
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native getWorld", t);
         }
@@ -474,15 +571,14 @@ public class NativePatchBukkit {
     public static String getRegistryData(String registryName) {
         try (Arena arena = Arena.ofConfined()) {
             MemorySegment nameStr = arena.allocateFrom(registryName);
-            MemorySegment resultPtr = (MemorySegment) getRegistryDataNative.invokeExact(nameStr);
+            MemorySegment out = arena.allocate(ValueLayout.ADDRESS);
 
-            if (resultPtr.equals(MemorySegment.NULL)) return null;
+            int status = (int) getRegistryDataNative.invokeExact(nameStr, out);
+            if (!check("rust_get_registry_data", status)) return null;
 
-            try {
-                return resultPtr.reinterpret(Long.MAX_VALUE).getString(0);
-            } finally {
-                freeRustString(resultPtr);
-            }
+            return takeRustString(out);
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to get registry data: " + registryName, t);
         }
@@ -512,7 +608,7 @@ public class NativePatchBukkit {
             MemorySegment soundCategoryStr = arena.allocateFrom(soundCategory);
             MemorySegment entityUuidStr = arena.allocateFrom(entityUuid.toString());
 
-            playerEntityPlaySoundNative.invokeExact(
+            int status = (int) playerEntityPlaySoundNative.invokeExact(
                 playerUuidStr,
                 soundNameStr,
                 soundCategoryStr,
@@ -520,6 +616,9 @@ public class NativePatchBukkit {
                 volume,
                 pitch
             );
+            check("rust_player_entity_play_sound", status);
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native entityPlaySound", t);
         }
@@ -552,7 +651,7 @@ public class NativePatchBukkit {
             MemorySegment soundNameStr = arena.allocateFrom(soundName);
             MemorySegment soundCategoryStr = arena.allocateFrom(soundCategory);
 
-            playerPlaySoundNative.invokeExact(
+            int status = (int) playerPlaySoundNative.invokeExact(
                 playerUuidStr,
                 soundNameStr,
                 soundCategoryStr,
@@ -562,6 +661,9 @@ public class NativePatchBukkit {
                 volume,
                 pitch
             );
+            check("rust_player_play_sound", status);
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call native playerPlaySound", t);
         }
@@ -575,13 +677,16 @@ public class NativePatchBukkit {
             MemorySegment eventTypeStr = arena.allocateFrom(event.getEventName());
             MemorySegment eventDataStr = arena.allocateFrom(eventDataJson);
 
-            boolean handled = (boolean) callEventNative.invokeExact(eventTypeStr, eventDataStr);
+            int status = (int) callEventNative.invokeExact(eventTypeStr, eventDataStr);
+            boolean handled = check("rust_call_event", status);
 
             // If Pumpkin handled it and event is Cancellable, we need to read back
             // the cancelled state. This is handled by Rust calling back to update
             // the event object directly.
 
             return handled;
+        } catch (NativeCallException e) {
+            throw e;
         } catch (Throwable t) {
             throw new RuntimeException("Failed to call event: " + event.getEventName(), t);
         }
@@ -1,151 +1,45 @@
 package org.patchbukkit.command;
 
-import java.util.Set;
-import java.util.UUID;
-
-import org.bukkit.Server;
+import org.bukkit.Bukkit;
+import org.bukkit.World;
 import org.bukkit.block.Block;
 import org.bukkit.command.BlockCommandSender;
-import org.bukkit.permissions.Permission;
-import org.bukkit.permissions.PermissionAttachment;
-import org.bukkit.permissions.PermissionAttachmentInfo;
-import org.bukkit.plugin.Plugin;
 import org.jetbrains.annotations.NotNull;
-import org.jetbrains.annotations.Nullable;
-
-import net.kyori.adventure.text.Component;
-
-public class PatchBukkitBlockCommandSender implements BlockCommandSender {
-    @NotNull
-    public Block getBlock() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'getBlock'");
-    }
-
-    @Override
-    public boolean isPermissionSet(@NotNull String name) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'isPermissionSet'");
-    }
-
-    @Override
-    public boolean isPermissionSet(@NotNull Permission perm) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'isPermissionSet'");
-    }
-
-    @Override
-    public boolean hasPermission(@NotNull String name) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'hasPermission'");
-    }
-
-    @Override
-    public boolean hasPermission(@NotNull Permission perm) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'hasPermission'");
-    }
-
-    @Override
-    public @NotNull PermissionAttachment addAttachment(@NotNull Plugin plugin, @NotNull String name, boolean value) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'addAttachment'");
-    }
-
-    @Override
-    public @NotNull PermissionAttachment addAttachment(@NotNull Plugin plugin) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'addAttachment'");
-    }
 
-    @Override
-    public @Nullable PermissionAttachment addAttachment(@NotNull Plugin plugin, @NotNull String name, boolean value,
-            int ticks) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'addAttachment'");
-    }
+/**
+ * Command sender for commands run by a Pumpkin command block.
+ */
+public class PatchBukkitBlockCommandSender
+    extends PatchBukkitCommandSender
+    implements BlockCommandSender
+{
 
-    @Override
-    public @Nullable PermissionAttachment addAttachment(@NotNull Plugin plugin, int ticks) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'addAttachment'");
-    }
+    private final World world;
+    private final int x;
+    private final int y;
+    private final int z;
 
-    @Override
-    public void removeAttachment(@NotNull PermissionAttachment attachment) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'removeAttachment'");
+    public PatchBukkitBlockCommandSender(@NotNull World world, int x, int y, int z) {
+        super(Bukkit.getServer(), "@");
+        this.world = world;
+        this.x = x;
+        this.y = y;
+        this.z = z;
     }
 
     @Override
-    public void recalculatePermissions() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'recalculatePermissions'");
-    }
-
-    @Override
-    public @NotNull Set<PermissionAttachmentInfo> getEffectivePermissions() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'getEffectivePermissions'");
+    public @NotNull Block getBlock() {
+        return this.world.getBlockAt(this.x, this.y, this.z);
     }
 
     @Override
     public boolean isOp() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'isOp'");
+        // Command blocks run with operator permissions, as in vanilla
+        return true;
     }
 
     @Override
     public void setOp(boolean value) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'setOp'");
-    }
-
-    @Override
-    public void sendMessage(@NotNull String message) {
-        throw new UnsupportedOperationException("Unimplemented method 'sendMessage'");
-    }
-
-    @Override
-    public void sendMessage(@NotNull String... messages) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'sendMessage'");
-    }
-
-    @Override
-    public void sendMessage(@Nullable UUID sender, @NotNull String message) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'sendMessage'");
-    }
-
-    @Override
-    public void sendMessage(@Nullable UUID sender, @NotNull String... messages) {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'sendMessage'");
+        // Command blocks are always op; ignore attempts to change.
     }
-
-    @Override
-    public @NotNull Server getServer() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'getServer'");
-    }
-
-    @Override
-    public @NotNull String getName() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'getName'");
-    }
-
-    @Override
-    public @NotNull Spigot spigot() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'spigot'");
-    }
-
-    @Override
-    public @NotNull Component name() {
-        // TODO Auto-generated method stub
-        throw new UnsupportedOperationException("Unimplemented method 'name'");
-    }
-
 }
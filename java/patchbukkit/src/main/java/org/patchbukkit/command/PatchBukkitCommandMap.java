@@ -1,18 +1,27 @@
 package org.patchbukkit.command;
 
+import com.destroystokyo.paper.event.server.AsyncTabCompleteEvent;
+import com.google.gson.JsonArray;
+import com.google.gson.JsonObject;
 import java.util.ArrayList;
 import java.util.Arrays;
 import java.util.Collections;
 import java.util.HashMap;
 import java.util.List;
 import java.util.Map;
+import net.kyori.adventure.text.Component;
+import net.kyori.adventure.text.serializer.legacy.LegacyComponentSerializer;
+import org.bukkit.Bukkit;
 import org.bukkit.Location;
 import org.bukkit.command.Command;
 import org.bukkit.command.CommandException;
 import org.bukkit.command.CommandMap;
 import org.bukkit.command.CommandSender;
+import org.bukkit.command.PluginCommand;
+import org.bukkit.plugin.Plugin;
 import org.jetbrains.annotations.NotNull;
 import org.jetbrains.annotations.Nullable;
+import org.patchbukkit.PatchBukkitServer;
 
 public class PatchBukkitCommandMap implements CommandMap {
     // We use Command as the value to support both PluginCommand and vanilla commands
@@ -80,6 +89,17 @@ public class PatchBukkitCommandMap implements CommandMap {
         }
     }
 
+    /**
+     * Removes every command owned by a plugin, under all of its labels and aliases.
+     *
+     * @param plugin The plugin whose commands are removed
+     */
+    public void unregisterPluginCommands(@NotNull Plugin plugin) {
+        knownCommands.values().removeIf(command ->
+            command instanceof PluginCommand pluginCommand && pluginCommand.getPlugin() == plugin
+        );
+    }
+
     @Override
     public void clearCommands() {
         knownCommands.clear();
@@ -125,4 +145,50 @@ public class PatchBukkitCommandMap implements CommandMap {
 
         return null;
     }
+
+    public @NotNull String tabCompleteWithTooltips(@NotNull CommandSender sender, @NotNull String cmdLine) {
+        return tabCompleteWithTooltips(sender, cmdLine, null);
+    }
+
+    /**
+     * Called from Rust to tab complete a command line, keeping tooltips.
+     *
+     * Plugins handling Paper's AsyncTabCompleteEvent provide the completions,
+     * otherwise the command's own tab completer is used.
+     *
+     * @return JSON array of objects with a "text" and an optional legacy formatted "tooltip"
+     */
+    public @NotNull String tabCompleteWithTooltips(@NotNull CommandSender sender, @NotNull String cmdLine, @Nullable Location location) {
+        String buffer = cmdLine.startsWith("/") ? cmdLine : "/" + cmdLine;
+        AsyncTabCompleteEvent event = new AsyncTabCompleteEvent(sender, buffer, true, location);
+        if (Bukkit.getServer() instanceof PatchBukkitServer server) {
+            server.getEventManager().callEventJavaOnly(event);
+        }
+
+        List<AsyncTabCompleteEvent.Completion> completions = new ArrayList<>();
+        if (event.isCancelled()) {
+            return new JsonArray().toString();
+        } else if (event.isHandled()) {
+            completions.addAll(event.completions());
+        } else {
+            List<String> legacy = tabComplete(sender, cmdLine, location);
+            if (legacy != null) {
+                for (String completion : legacy) {
+                    completions.add(AsyncTabCompleteEvent.Completion.completion(completion));
+                }
+            }
+        }
+
+        JsonArray result = new JsonArray();
+        for (AsyncTabCompleteEvent.Completion completion : completions) {
+            JsonObject entry = new JsonObject();
+            entry.addProperty("text", completion.suggestion());
+            Component tooltip = completion.tooltip();
+            if (tooltip != null) {
+                entry.addProperty("tooltip", LegacyComponentSerializer.legacySection().serialize(tooltip));
+            }
+            result.add(entry);
+        }
+        return result.toString();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+package org.patchbukkit.command;
+
+import java.net.InetAddress;
+import java.net.InetSocketAddress;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.UUID;
+
+import org.bukkit.Bukkit;
+import org.bukkit.command.RemoteConsoleCommandSender;
+import org.jetbrains.annotations.NotNull;
+import org.jetbrains.annotations.Nullable;
+
+/**
+ * Command sender for commands received over Pumpkin's RCON.
+ *
+ * Messages are buffered so Rust can hand them back to the RCON client
+ * once the command has finished.
+ */
+public class PatchBukkitRemoteConsoleCommandSender
+    extends PatchBukkitCommandSender
+    implements RemoteConsoleCommandSender
+{
+
+    private final List<String> output = new ArrayList<>();
+
+    public PatchBukkitRemoteConsoleCommandSender() {
+        super(Bukkit.getServer(), "Rcon");
+    }
+
+    @Override
+    public void sendMessage(@NotNull String message) {
+        if (message == null) {
+            return;
+        }
+        synchronized (this.output) {
+            this.output.add(message);
+        }
+    }
+
+    @Override
+    public void sendMessage(@Nullable UUID sender, @NotNull String message) {
+        this.sendMessage(message);
+    }
+
+    /**
+     * Called from Rust after dispatching a command to collect its output.
+     */
+    public List<String> drainOutput() {
+        synchronized (this.output) {
+            List<String> drained = new ArrayList<>(this.output);
+            this.output.clear();
+            return drained;
+        }
+    }
+
+    @Override
+    public @NotNull InetSocketAddress getAddress() {
+        // Pumpkin does not expose the address of the RCON client
+        return new InetSocketAddress(InetAddress.getLoopbackAddress(), 0);
+    }
+
+    @Override
+    public boolean isOp() {
+        return true;
+    }
+
+    @Override
+    public void setOp(boolean value) {
+        // RCON is always op; ignore attempts to change.
+    }
+}
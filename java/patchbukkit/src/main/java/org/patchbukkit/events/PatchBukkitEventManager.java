@@ -21,15 +21,22 @@ import co.aikar.timings.TimedEventExecutor;
 import org.jetbrains.annotations.NotNull;
 
 import java.lang.reflect.Method;
+import java.util.ArrayList;
 import java.util.Arrays;
+import java.util.Collections;
 import java.util.HashMap;
 import java.util.HashSet;
+import java.util.List;
 import java.util.Map;
 import java.util.Set;
+import java.util.concurrent.ConcurrentHashMap;
 import java.util.logging.Level;
 
 public class PatchBukkitEventManager {
 
+    /** The event class owning each handler list listeners were registered with. */
+    private static final Map<HandlerList, String> HANDLER_LIST_EVENTS = new ConcurrentHashMap<>();
+
     private final Server server;
 
     public PatchBukkitEventManager(Server server) {
@@ -55,7 +62,7 @@ public class PatchBukkitEventManager {
     * Java-only event dispatch for events that don't have Pumpkin equivalents.
     * Used for custom plugin events or unsupported Bukkit events.
     */
-    private void callEventJavaOnly(@NotNull Event event) {
+    public void callEventJavaOnly(@NotNull Event event) {
         HandlerList handlers = event.getHandlers();
         RegisteredListener[] listeners = handlers.getRegisteredListeners();
 
@@ -155,12 +162,41 @@ public class PatchBukkitEventManager {
 
     }
 
+    /**
+     * Called from Rust (via j4rs) for {@code /patchbukkit events}.
+     *
+     * Lists every Bukkit listener the plugin registered, whether or not Pumpkin forwards
+     * its event, one per line: the event class, the priority, whether it ignores cancelled
+     * events and the listener class, separated by spaces.
+     *
+     * @param plugin The plugin whose listeners are listed
+     */
+    public static List<String> describeListeners(@NotNull Plugin plugin) {
+        List<String> lines = new ArrayList<>();
+        for (Map.Entry<HandlerList, String> entry : HANDLER_LIST_EVENTS.entrySet()) {
+            for (RegisteredListener listener : entry.getKey().getRegisteredListeners()) {
+                if (listener.getPlugin() != plugin) continue;
+                lines.add(
+                    entry.getValue()
+                        + " " + listener.getPriority()
+                        + " " + listener.isIgnoringCancelled()
+                        + " " + listener.getListener().getClass().getName()
+                );
+            }
+        }
+        Collections.sort(lines);
+        return lines;
+    }
+
     @NotNull
     private HandlerList getEventListeners(@NotNull Class<? extends Event> type) {
         try {
-            Method method = this.getRegistrationClass(type).getDeclaredMethod("getHandlerList");
+            Class<? extends Event> registrationClass = this.getRegistrationClass(type);
+            Method method = registrationClass.getDeclaredMethod("getHandlerList");
             method.setAccessible(true);
-            return (HandlerList) method.invoke(null);
+            HandlerList handlers = (HandlerList) method.invoke(null);
+            HANDLER_LIST_EVENTS.putIfAbsent(handlers, registrationClass.getName());
+            return handlers;
         } catch (Exception e) {
             throw new IllegalPluginAccessException(e.toString());
         }
@@ -2,11 +2,21 @@ use std::{collections::HashSet, error::Error, fs, path::PathBuf};
 
 use j4rs::{JvmBuilder, LocalJarArtifact, MavenArtifact, MavenArtifactRepo, MavenSettings};
 
+/// The bundled paper-api, which java/patchbukkit/build.gradle.kts must compile against
+const PAPER_API_VERSION: &str = "1.21.11-R0.1-SNAPSHOT";
+
 fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo::rerun-if-changed=rust/src/build.rs");
     println!("cargo::rerun-if-changed=java/build/libs/");
     env_logger::init();
 
+    // `BUNDLED_API_VERSION` in src/config/version.rs, e.g. 1.21.11
+    let api_version = PAPER_API_VERSION.split('-').next().unwrap();
+    println!(
+        "cargo::rustc-env=PATCHBUKKIT_PAPER_API_VERSION={}",
+        api_version
+    );
+
     let dependencies = [
         ("com.google.guava", "guava", "33.3.1-jre"),
         (
@@ -78,7 +88,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         ("org.eclipse.sisu", "org.eclipse.sisu.inject", "0.9.0.M2"),
         ("org.apache.commons", "commons-lang3", "3.12.0"),
         ("net.kyori", "option", "1.1.0"),
-        ("io.papermc.paper", "paper-api", "1.21.11-R0.1-SNAPSHOT"),
+        ("io.papermc.paper", "paper-api", PAPER_API_VERSION),
     ];
 
     let base = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
@@ -134,6 +144,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     java_path.pop();
     let java_path = java_path.join("java");
 
+    let gradle_build = java_path.join("patchbukkit").join("build.gradle.kts");
+    println!("cargo::rerun-if-changed={}", gradle_build.display());
+    let paper_api = format!("io.papermc.paper:paper-api:{}", PAPER_API_VERSION);
+    if !fs::read_to_string(&gradle_build)?.contains(&paper_api) {
+        panic!(
+            "{} must depend on {}, the paper-api bundled by build.rs",
+            gradle_build.display(),
+            paper_api
+        );
+    }
+
     let patchbukkit_jar = java_path
         .join("patchbukkit")
         .join("build")
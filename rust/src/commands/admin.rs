@@ -0,0 +1,339 @@
+use pumpkin::command::{
+    CommandExecutor, CommandSender,
+    args::{Arg, ConsumedArgs, simple::SimpleArgConsumer},
+    dispatcher::CommandError,
+    tree::{
+        CommandTree,
+        builder::{argument, literal},
+    },
+};
+use pumpkin_util::text::TextComponent;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::java::{
+    jvm::{
+        channel::{self, CommandKind},
+        commands::{JvmCommand, PluginInfo},
+    },
+    plugin::manager::PluginState,
+};
+
+const NAMES: [&str; 2] = ["patchbukkit", "pb"];
+const DESCRIPTION: &str = "Inspect and manage the Bukkit plugins run by PatchBukkit";
+const ARG_PLUGIN: &str = "plugin";
+
+/// Pumpkin permission node guarding `/patchbukkit`
+pub const PERMISSION: &str = "patchbukkit:command.patchbukkit";
+
+#[derive(Clone, Copy)]
+enum Action {
+    Enable,
+    Disable,
+    Reload,
+}
+
+impl Action {
+    fn command(self, name: String, respond_to: oneshot::Sender<anyhow::Result<()>>) -> JvmCommand {
+        match self {
+            Action::Enable => JvmCommand::EnablePlugin { name, respond_to },
+            Action::Disable => JvmCommand::DisablePlugin { name, respond_to },
+            Action::Reload => JvmCommand::ReloadPlugin { name, respond_to },
+        }
+    }
+}
+
+struct ListExecutor {
+    command_tx: mpsc::Sender<JvmCommand>,
+}
+
+struct InfoExecutor {
+    command_tx: mpsc::Sender<JvmCommand>,
+}
+
+struct ActionExecutor {
+    command_tx: mpsc::Sender<JvmCommand>,
+    action: Action,
+}
+
+struct EventsExecutor {
+    command_tx: mpsc::Sender<JvmCommand>,
+}
+
+struct QueueExecutor {
+    command_tx: mpsc::Sender<JvmCommand>,
+}
+
+/// Sends a request to the JVM worker and waits for its answer
+async fn request<T>(
+    command_tx: &mpsc::Sender<JvmCommand>,
+    kind: CommandKind,
+    command: impl FnOnce(oneshot::Sender<T>) -> JvmCommand,
+) -> Result<T, CommandError> {
+    channel::request(command_tx, kind, command)
+        .await
+        .map_err(|e| CommandError::CommandFailed(Box::new(TextComponent::text(e.to_string()))))
+}
+
+fn plugin_arg<'a>(args: &'a ConsumedArgs<'a>) -> Result<&'a str, CommandError> {
+    match args.get(ARG_PLUGIN) {
+        Some(Arg::Simple(name)) => Ok(*name),
+        _ => Err(CommandError::InvalidConsumption(Some(ARG_PLUGIN.into()))),
+    }
+}
+
+fn state_color(state: PluginState) -> &'static str {
+    match state {
+        PluginState::Enabled => "§a",
+        PluginState::Errored => "§c",
+        PluginState::Disabled | PluginState::Skipped => "§7",
+        PluginState::Registered | PluginState::Loaded => "§e",
+    }
+}
+
+fn join_or_none(values: &[String]) -> String {
+    if values.is_empty() {
+        "none".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+/// `org.bukkit.event.player.PlayerJoinEvent` is shown as `PlayerJoinEvent`
+fn simple_class_name(class: &str) -> &str {
+    class.rsplit('.').next().unwrap_or(class)
+}
+
+async fn send_line(sender: &CommandSender, line: &str) {
+    sender
+        .send_message(TextComponent::from_legacy_string(line))
+        .await;
+}
+
+impl CommandExecutor for ListExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a pumpkin::server::Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> pumpkin::command::CommandResult<'a> {
+        Box::pin(async move {
+            let plugins = request(&self.command_tx, CommandKind::Query, |respond_to| {
+                JvmCommand::ListPlugins { respond_to }
+            })
+            .await?;
+
+            send_line(
+                sender,
+                &format!("§6PatchBukkit plugins ({}):", plugins.len()),
+            )
+            .await;
+            for plugin in plugins {
+                send_line(
+                    sender,
+                    &format!(
+                        " §f{} §7{} {}{:?}",
+                        plugin.name,
+                        plugin.version,
+                        state_color(plugin.state),
+                        plugin.state
+                    ),
+                )
+                .await;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl CommandExecutor for InfoExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a pumpkin::server::Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> pumpkin::command::CommandResult<'a> {
+        Box::pin(async move {
+            let name = plugin_arg(args)?.to_string();
+            let info: Option<PluginInfo> =
+                request(&self.command_tx, CommandKind::Query, |respond_to| {
+                    JvmCommand::GetPluginInfo {
+                        name: name.clone(),
+                        respond_to,
+                    }
+                })
+                .await?;
+            let Some(info) = info else {
+                return Err(CommandError::CommandFailed(Box::new(TextComponent::text(
+                    format!("No PatchBukkit plugin named {}", name),
+                ))));
+            };
+
+            let commands: Vec<String> = info
+                .commands
+                .iter()
+                .map(|command| format!("/{}", command))
+                .collect();
+            let lines = [
+                format!(
+                    "§6{} §f{} {}{:?}",
+                    info.name,
+                    info.version,
+                    state_color(info.state),
+                    info.state
+                ),
+                format!("§7Authors: §f{}", join_or_none(&info.authors)),
+                format!("§7Depends: §f{}", join_or_none(&info.depends)),
+                format!("§7Soft depends: §f{}", join_or_none(&info.soft_depends)),
+                format!("§7Provides: §f{}", join_or_none(&info.provides)),
+                format!("§7Commands: §f{}", join_or_none(&commands)),
+            ];
+            for line in lines {
+                send_line(sender, &line).await;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl CommandExecutor for ActionExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a pumpkin::server::Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> pumpkin::command::CommandResult<'a> {
+        Box::pin(async move {
+            let name = plugin_arg(args)?.to_string();
+            let action = self.action;
+            let (running, done) = match action {
+                Action::Enable => ("Enabling", "Enabled"),
+                Action::Disable => ("Disabling", "Disabled"),
+                Action::Reload => ("Reloading", "Reloaded"),
+            };
+            send_line(sender, &format!("§7{} {}...", running, name)).await;
+
+            // The worker registers and unregisters Pumpkin commands, which waits for the
+            // dispatcher this command runs in, so the request must outlive this command
+            let command_tx = self.command_tx.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let result = channel::request(&command_tx, CommandKind::Lifecycle, |respond_to| {
+                    action.command(name.clone(), respond_to)
+                })
+                .await;
+
+                let line = match result {
+                    Ok(Ok(())) => format!("§a{} {}", done, name),
+                    Ok(Err(e)) => format!("§c{}", e),
+                    Err(e) => format!("§c{} {} was not completed: {}", running, name, e),
+                };
+                send_line(&sender, &line).await;
+            });
+            Ok(())
+        })
+    }
+}
+
+impl CommandExecutor for EventsExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a pumpkin::server::Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> pumpkin::command::CommandResult<'a> {
+        Box::pin(async move {
+            let plugins = request(&self.command_tx, CommandKind::Query, |respond_to| {
+                JvmCommand::ListEventListeners { respond_to }
+            })
+            .await?
+            .map_err(|e| {
+                CommandError::CommandFailed(Box::new(TextComponent::text(e.to_string())))
+            })?;
+
+            send_line(
+                sender,
+                "§6Bukkit listeners by plugin, §bblue§6 events are forwarded from Pumpkin:",
+            )
+            .await;
+            for (plugin, listeners) in plugins
+                .iter()
+                .filter(|(_, listeners)| !listeners.is_empty())
+            {
+                send_line(sender, &format!(" §f{}§7:", plugin)).await;
+                for listener in listeners {
+                    send_line(
+                        sender,
+                        &format!(
+                            "  {}{} §7{} ({}){}",
+                            if listener.forwarded { "§b" } else { "§f" },
+                            simple_class_name(&listener.event),
+                            listener.priority,
+                            simple_class_name(&listener.listener),
+                            if listener.ignore_cancelled {
+                                ", ignores cancelled"
+                            } else {
+                                ""
+                            }
+                        ),
+                    )
+                    .await;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl CommandExecutor for QueueExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a pumpkin::server::Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> pumpkin::command::CommandResult<'a> {
+        Box::pin(async move {
+            let stats = channel::stats(&self.command_tx);
+            let lines = [
+                "§6PatchBukkit JVM command queue:".to_string(),
+                format!("§7Queued: §f{}/{}", stats.depth, stats.capacity),
+                format!("§7Highest: §f{}", stats.high_water),
+                format!("§7Dropped events: §f{}", stats.dropped_events),
+                format!("§7Timed out requests: §f{}", stats.timeouts),
+            ];
+            for line in lines {
+                send_line(sender, &line).await;
+            }
+            Ok(())
+        })
+    }
+}
+
+pub fn init_patchbukkit_command(command_tx: mpsc::Sender<JvmCommand>) -> CommandTree {
+    let action = |name: &'static str, action: Action| {
+        literal(name).then(
+            argument(ARG_PLUGIN, SimpleArgConsumer).execute(ActionExecutor {
+                command_tx: command_tx.clone(),
+                action,
+            }),
+        )
+    };
+
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(literal("list").execute(ListExecutor {
+            command_tx: command_tx.clone(),
+        }))
+        .then(literal("info").then(
+            argument(ARG_PLUGIN, SimpleArgConsumer).execute(InfoExecutor {
+                command_tx: command_tx.clone(),
+            }),
+        ))
+        .then(action("enable", Action::Enable))
+        .then(action("disable", Action::Disable))
+        .then(action("reload", Action::Reload))
+        .then(literal("events").execute(EventsExecutor {
+            command_tx: command_tx.clone(),
+        }))
+        .then(literal("queue").execute(QueueExecutor {
+            command_tx: command_tx.clone(),
+        }))
+}
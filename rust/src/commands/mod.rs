@@ -1,9 +1,13 @@
-use std::borrow::Cow;
+pub mod admin;
+pub mod tab_complete;
+
+use std::{borrow::Cow, sync::Arc};
 
 use pumpkin::{
     command::{
         CommandExecutor, CommandSender,
         args::{Arg, ArgumentConsumer, GetClientSideArgParser, SuggestResult},
+        dispatcher::CommandError,
         tree::{CommandTree, builder::argument},
     },
     entity::EntityBase,
@@ -11,15 +15,41 @@ use pumpkin::{
 use pumpkin_protocol::java::client::play::{
     ArgumentType, StringProtoArgBehavior, SuggestionProviders,
 };
-use tokio::sync::{mpsc, oneshot};
+use pumpkin_util::{math::position::BlockPos, text::TextComponent};
+use tokio::sync::mpsc;
+use uuid::Uuid;
 
-use crate::java::jvm::commands::{JvmCommand, Location, Rotation};
+use crate::{
+    commands::tab_complete::TabCompleter,
+    java::jvm::{
+        channel::{CommandKind, request},
+        commands::{JvmCommand, Location, Rotation},
+    },
+};
 
 const ARG_ANY: &str = "any";
 
 pub struct JavaCommandExecutor {
     pub cmd_name: String,
+    /// The name or alias this executor is registered under, passed to Java as typed
+    pub label: String,
+    pub plugin_name: String,
     pub command_tx: mpsc::Sender<JvmCommand>,
+    /// `usage` from `plugin.yml`, which Bukkit sends when `onCommand` returns false
+    pub usage: Option<String>,
+    pub permission: Option<CommandPermission>,
+}
+
+/// A `plugin.yml` command permission checked before the command reaches Java.
+///
+/// Pumpkin answers a missing command permission with its own generic message, so
+/// commands declaring a `permission-message` are checked here instead.
+#[derive(Clone)]
+pub struct CommandPermission {
+    /// Pumpkin permission node
+    pub node: String,
+    /// Message sent to senders lacking the permission
+    pub message: String,
 }
 
 #[derive(Clone)]
@@ -27,10 +57,16 @@ pub enum SimpleCommandSender {
     Console,
     /// UUID
     Player(String),
+    Rcon,
+    CommandBlock {
+        world: Uuid,
+        position: BlockPos,
+    },
 }
 
 pub struct AnyCommandNode {
-    command_tx: mpsc::Sender<JvmCommand>,
+    plugin_name: String,
+    tab_completer: Arc<TabCompleter>,
 }
 
 impl GetClientSideArgParser for AnyCommandNode {
@@ -93,19 +129,9 @@ impl ArgumentConsumer for AnyCommandNode {
         let command_sender: SimpleCommandSender = sender.into();
 
         Box::pin(async move {
-            let (tx, rx) = oneshot::channel();
-            self.command_tx
-                .send(JvmCommand::GetCommandTabComplete {
-                    command_sender,
-                    full_command: input.to_string(),
-                    respond_to: tx,
-                    location,
-                })
+            self.tab_completer
+                .complete(&self.plugin_name, command_sender, input, location)
                 .await
-                .unwrap();
-
-            let suggestion = rx.await.unwrap();
-            suggestion
         })
     }
 }
@@ -113,12 +139,15 @@ impl ArgumentConsumer for AnyCommandNode {
 impl Into<SimpleCommandSender> for &CommandSender {
     fn into(self) -> SimpleCommandSender {
         match self {
-            CommandSender::Rcon(_mutex) => todo!(),
+            CommandSender::Rcon(_output) => SimpleCommandSender::Rcon,
             CommandSender::Console => SimpleCommandSender::Console,
             CommandSender::Player(player) => {
                 SimpleCommandSender::Player(player.gameprofile.id.to_string())
             }
-            CommandSender::CommandBlock(_block_entity, _world) => todo!(),
+            CommandSender::CommandBlock(block_entity, world) => SimpleCommandSender::CommandBlock {
+                world: world.uuid,
+                position: block_entity.get_position(),
+            },
         }
     }
 }
@@ -131,47 +160,143 @@ impl CommandExecutor for JavaCommandExecutor {
         args: &'a pumpkin::command::args::ConsumedArgs<'a>,
     ) -> pumpkin::command::CommandResult<'a> {
         Box::pin(async move {
+            if let Some(permission) = &self.permission
+                && !sender.has_permission(&permission.node).await
+            {
+                return Err(CommandError::CommandFailed(Box::new(
+                    TextComponent::from_legacy_string(&permission.message),
+                )));
+            }
+
             let full_command = match args.get(ARG_ANY) {
-                Some(Arg::Msg(msg)) => format!("/{} {}", self.cmd_name, msg),
-                _ => format!("/{}", self.cmd_name),
+                Some(Arg::Msg(msg)) => format!("/{} {}", self.label, msg),
+                _ => format!("/{}", self.label),
             };
 
-            let (tx, _rx) = oneshot::channel();
-            self.command_tx
-                .send(JvmCommand::TriggerCommand {
+            let result = request(&self.command_tx, CommandKind::Command, |respond_to| {
+                JvmCommand::TriggerCommand {
                     full_command: full_command,
-                    respond_to: tx,
+                    respond_to,
                     command_sender: sender.into(),
-                })
-                .await
-                .unwrap();
-            Ok(())
+                }
+            })
+            .await;
+
+            let outcome = match result {
+                Ok(Ok(outcome)) => outcome,
+                Ok(Err(e)) => {
+                    log::error!(
+                        "Plugin {} failed to execute command /{}: {:?}",
+                        self.plugin_name,
+                        self.cmd_name,
+                        e
+                    );
+                    return Err(CommandError::CommandFailed(Box::new(TextComponent::text(
+                        format!(
+                            "An internal error occurred in plugin {} while attempting to perform this command",
+                            self.plugin_name
+                        ),
+                    ))));
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Command /{} of plugin {} was not completed: {}",
+                        self.cmd_name,
+                        self.plugin_name,
+                        e
+                    );
+                    return Err(CommandError::CommandFailed(Box::new(TextComponent::text(
+                        "The command could not be completed",
+                    ))));
+                }
+            };
+
+            if !outcome.found {
+                return Err(CommandError::CommandFailed(Box::new(TextComponent::text(
+                    format!("Unknown command /{}", self.label),
+                ))));
+            }
+
+            let mut output = outcome.output;
+            // `PluginCommand.execute` already sent the usage, as the last lines of the output
+            // when it was captured, which become the failure message instead
+            let usage = if outcome.success {
+                None
+            } else {
+                let usage_lines = self
+                    .usage
+                    .as_deref()
+                    .map_or(1, |usage| usage.lines().count());
+                let usage_start = output.len().saturating_sub(usage_lines);
+                Some(output.split_off(usage_start))
+            };
+
+            for line in output {
+                sender
+                    .send_message(TextComponent::from_legacy_string(&line))
+                    .await;
+            }
+
+            // A false `onCommand` fails the command even when nothing was captured, so
+            // `/execute` sees the failure
+            let Some(usage) = usage else {
+                return Ok(());
+            };
+            let message = if !usage.is_empty() {
+                usage.join("\n")
+            } else if let Some(declared) = &self.usage {
+                declared.replace("<command>", &self.label)
+            } else {
+                format!("Command /{} failed", self.label)
+            };
+            Err(CommandError::CommandFailed(Box::new(
+                TextComponent::from_legacy_string(&message),
+            )))
         })
     }
 }
 
+/// Builds the Pumpkin command tree of one label of a Java command.
+///
+/// Every alias gets a tree of its own, so the executor knows which label was typed.
+#[allow(clippy::too_many_arguments)]
 pub fn init_java_command(
     cmd_name: impl Into<String>,
+    label: impl Into<String>,
+    plugin_name: impl Into<String>,
     command_tx: mpsc::Sender<JvmCommand>,
-    names: impl IntoIterator<Item: Into<String>>,
+    tab_completer: Arc<TabCompleter>,
     description: impl Into<Cow<'static, str>>,
+    usage: Option<String>,
+    permission: Option<CommandPermission>,
 ) -> CommandTree {
     let cmd_name = cmd_name.into();
-    CommandTree::new(names, description)
+    let label = label.into();
+    let plugin_name = plugin_name.into();
+    CommandTree::new([label.clone()], description)
         .execute(JavaCommandExecutor {
             cmd_name: cmd_name.clone(),
+            label: label.clone(),
+            plugin_name: plugin_name.clone(),
             command_tx: command_tx.clone(),
+            usage: usage.clone(),
+            permission: permission.clone(),
         })
         .then(
             argument(
                 ARG_ANY,
                 AnyCommandNode {
-                    command_tx: command_tx.clone(),
+                    plugin_name: plugin_name.clone(),
+                    tab_completer,
                 },
             )
             .execute(JavaCommandExecutor {
                 cmd_name,
+                label,
+                plugin_name,
                 command_tx,
+                usage,
+                permission,
             }),
         )
 }
@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use pumpkin::command::dispatcher::CommandError;
+use pumpkin_protocol::java::client::play::CommandSuggestion;
+use tokio::sync::mpsc;
+
+use crate::{
+    commands::SimpleCommandSender,
+    config::patchbukkit::CommandsConfig,
+    java::jvm::{
+        channel::{CommandKind, JvmCallError, request_within},
+        commands::{JvmCommand, Location},
+    },
+};
+
+type Suggestions = Option<Vec<CommandSuggestion>>;
+
+/// Plugin, sender and input of a cached completion
+type CacheKey = (String, String, String);
+
+/// Marks a tab completion request as superseded once the same sender asks again
+pub struct TabCompleteTicket {
+    generation: u64,
+    latest: Arc<AtomicU64>,
+}
+
+impl TabCompleteTicket {
+    /// Whether a newer request from the same sender has been issued since this one
+    pub fn is_stale(&self) -> bool {
+        self.latest.load(Ordering::Acquire) != self.generation
+    }
+}
+
+/// Forwards tab completion requests to the JVM without letting a slow plugin stall the client.
+///
+/// Each request waits at most the configured timeout, older requests still queued for a sender
+/// are skipped once it types again, and answers are reused per plugin, sender and input for a
+/// short window.
+pub struct TabCompleter {
+    command_tx: mpsc::Sender<JvmCommand>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<CacheKey, (Instant, Suggestions)>>,
+    /// Latest request per sender, only kept while one of its tickets is alive
+    generations: Mutex<HashMap<String, Arc<AtomicU64>>>,
+}
+
+impl TabCompleter {
+    pub fn new(command_tx: mpsc::Sender<JvmCommand>, config: &CommandsConfig) -> Self {
+        Self {
+            command_tx,
+            timeout: Duration::from_millis(config.tab_complete_timeout_ms),
+            cache_ttl: Duration::from_millis(config.tab_complete_cache_ms),
+            cache: Mutex::new(HashMap::new()),
+            generations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn complete(
+        &self,
+        plugin_name: &str,
+        command_sender: SimpleCommandSender,
+        full_command: &str,
+        location: Option<Location>,
+    ) -> Result<Suggestions, CommandError> {
+        // Completions often depend on the sender, such as its permissions or location
+        let key = (
+            plugin_name.to_string(),
+            sender_key(&command_sender),
+            full_command.to_string(),
+        );
+        if let Some(suggestions) = self.cached(&key) {
+            return Ok(suggestions);
+        }
+
+        let ticket = self.issue_ticket(&command_sender);
+        let result = request_within(
+            &self.command_tx,
+            CommandKind::TabComplete,
+            self.timeout,
+            |respond_to| JvmCommand::GetCommandTabComplete {
+                command_sender,
+                full_command: full_command.to_string(),
+                respond_to,
+                location,
+                ticket,
+            },
+        )
+        .await;
+
+        match result {
+            Ok(Ok(suggestions)) => {
+                self.store(key, suggestions.clone());
+                Ok(suggestions)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(JvmCallError::Timeout { after, .. }) => {
+                log::warn!(
+                    "Plugin {} did not provide tab completions for `{}` within {}ms",
+                    plugin_name,
+                    full_command,
+                    after.as_millis()
+                );
+                Ok(Some(Vec::new()))
+            }
+            Err(e) => {
+                log::warn!("No tab completions for `{}`: {}", full_command, e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn issue_ticket(&self, command_sender: &SimpleCommandSender) -> TabCompleteTicket {
+        let mut generations = self.generations.lock().unwrap();
+        // A sender without a request in flight has nothing to supersede, so its counter can
+        // start over, which keeps senders that left from piling up
+        generations.retain(|_, latest| Arc::strong_count(latest) > 1);
+        let latest = generations
+            .entry(sender_key(command_sender))
+            .or_default()
+            .clone();
+        let generation = latest.fetch_add(1, Ordering::AcqRel) + 1;
+        TabCompleteTicket { generation, latest }
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<Suggestions> {
+        let cache = self.cache.lock().unwrap();
+        let (stored_at, suggestions) = cache.get(key)?;
+        (stored_at.elapsed() < self.cache_ttl).then(|| suggestions.clone())
+    }
+
+    fn store(&self, key: CacheKey, suggestions: Suggestions) {
+        if self.cache_ttl.is_zero() {
+            return;
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (stored_at, _)| stored_at.elapsed() < self.cache_ttl);
+        cache.insert(key, (Instant::now(), suggestions));
+    }
+}
+
+fn sender_key(command_sender: &SimpleCommandSender) -> String {
+    match command_sender {
+        SimpleCommandSender::Console => "console".to_string(),
+        SimpleCommandSender::Player(uuid) => uuid.clone(),
+        SimpleCommandSender::Rcon => "rcon".to_string(),
+        SimpleCommandSender::CommandBlock { world, position } => format!(
+            "{}:{},{},{}",
+            world, position.0.x, position.0.y, position.0.z
+        ),
+    }
+}
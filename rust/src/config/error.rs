@@ -0,0 +1,366 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, de::IgnoredAny};
+use serde_saphyr::{Location, Spanned};
+
+use crate::config::{
+    paper::{self, PAPER_PLUGIN_CONFIG},
+    spigot::{self, SPIGOT_PLUGIN_CONFIG},
+};
+
+/// A line and column in a plugin description file, both starting at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct YamlLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl YamlLocation {
+    fn from_saphyr(location: Location) -> Option<Self> {
+        (location != Location::UNKNOWN).then(|| Self {
+            line: location.line() as usize,
+            column: location.column() as usize,
+        })
+    }
+}
+
+/// What is wrong with a `plugin.yml` or `paper-plugin.yml`
+#[derive(Debug, PartialEq)]
+pub enum PluginConfigErrorKind {
+    /// A required field such as `name` or `main` is absent
+    MissingField { field: String },
+    /// A field holds a value of the wrong type, e.g. a list where a string is expected
+    InvalidType { detail: String },
+    /// `load` (or a dependency's `load`) is not one of the known values
+    UnknownLoadOrder { value: String },
+    /// A permission `default` (or `default-permission`) is not one of the known values
+    InvalidPermissionDefault { value: String },
+    /// `name` has characters Bukkit does not allow or names one of PatchBukkit's folders
+    InvalidPluginName { name: String, reason: String },
+    /// The file is not valid YAML, or another problem serde reported
+    Syntax { message: String },
+}
+
+impl fmt::Display for PluginConfigErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginConfigErrorKind::MissingField { field } => {
+                write!(f, "missing required field `{}`", field)
+            }
+            PluginConfigErrorKind::InvalidType { detail } => write!(f, "{}", detail),
+            PluginConfigErrorKind::UnknownLoadOrder { value } => write!(
+                f,
+                "unknown load order `{}` (expected STARTUP or POSTWORLD, or BEFORE, AFTER or OMIT for dependencies)",
+                value
+            ),
+            PluginConfigErrorKind::InvalidPermissionDefault { value } => write!(
+                f,
+                "invalid permission default `{}` (expected true, false, op or notop)",
+                value
+            ),
+            PluginConfigErrorKind::InvalidPluginName { name, reason } => {
+                write!(f, "invalid plugin name `{}`, {}", name, reason)
+            }
+            PluginConfigErrorKind::Syntax { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A plugin description file that could not be parsed
+#[derive(Debug)]
+pub struct PluginConfigError {
+    pub file: &'static str,
+    pub location: Option<YamlLocation>,
+    pub kind: PluginConfigErrorKind,
+}
+
+impl PluginConfigError {
+    /// Wraps a serde-saphyr error raised while parsing `yaml` as `file`.
+    ///
+    /// The values PatchBukkit checks beyond their type, such as `load`, are checked again on
+    /// their own so the problem is reported with its kind and position. Other errors are
+    /// reported as serde-saphyr describes them.
+    pub fn new(file: &'static str, yaml: &str, error: serde_saphyr::Error) -> Self {
+        if let Some((kind, location)) = CheckedValues::first_problem(file, yaml) {
+            return Self {
+                file,
+                location,
+                kind,
+            };
+        }
+
+        let error = error.without_snippet();
+        let location = error.location().and_then(YamlLocation::from_saphyr);
+        let kind = match error {
+            // A scalar where a list or mapping is expected, or the other way around
+            serde_saphyr::Error::Unexpected { expected, .. } => {
+                PluginConfigErrorKind::InvalidType {
+                    detail: format!(
+                        "invalid type, expected {}",
+                        expected.trim_end_matches(" start")
+                    ),
+                }
+            }
+            serde_saphyr::Error::Message { msg, .. } => PluginConfigErrorKind::Syntax {
+                message: msg.clone(),
+            },
+            serde_saphyr::Error::Eof { .. } => PluginConfigErrorKind::Syntax {
+                message: "unexpected end of input".to_string(),
+            },
+            other => PluginConfigErrorKind::Syntax {
+                message: other.to_string(),
+            },
+        };
+
+        Self {
+            file,
+            location,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for PluginConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{}:{}:{}: ", self.file, location.line, location.column)?,
+            None => write!(f, "{}: ", self.file)?,
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for PluginConfigError {}
+
+/// The values of a plugin description that are checked beyond their type, read as plain
+/// strings along with where they are in the file
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CheckedValues {
+    name: Option<Spanned<String>>,
+    version: Option<IgnoredAny>,
+    main: Option<IgnoredAny>,
+    load: Option<Spanned<String>>,
+    permissions: Option<HashMap<String, CheckedPermission>>,
+    default_permission: Option<Spanned<String>>,
+    dependencies: Option<CheckedDependencies>,
+}
+
+#[derive(Deserialize)]
+struct CheckedPermission {
+    default: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+struct CheckedDependencies {
+    bootstrap: Option<HashMap<String, CheckedDependency>>,
+    server: Option<HashMap<String, CheckedDependency>>,
+}
+
+#[derive(Deserialize)]
+struct CheckedDependency {
+    load: Option<Spanned<String>>,
+}
+
+type Problem = (PluginConfigErrorKind, Option<YamlLocation>);
+
+impl CheckedValues {
+    /// The problem closest to the start of `yaml`, missing fields last, or `None` when
+    /// these values are fine or cannot be read at all
+    fn first_problem(file: &'static str, yaml: &str) -> Option<Problem> {
+        let values: Self = serde_saphyr::from_str(yaml).ok()?;
+        let mut problems = Vec::new();
+
+        for (field, value) in [
+            ("name", values.name.is_some()),
+            ("version", values.version.is_some()),
+            ("main", values.main.is_some()),
+        ] {
+            if !value {
+                problems.push((
+                    PluginConfigErrorKind::MissingField {
+                        field: field.to_string(),
+                    },
+                    None,
+                ));
+            }
+        }
+        if let Some(name) = &values.name {
+            check(&mut problems, name, spigot::parse_plugin_name);
+        }
+        if let Some(load) = &values.load {
+            check(&mut problems, load, str::parse::<spigot::LoadOrder>);
+        }
+
+        if file == SPIGOT_PLUGIN_CONFIG {
+            let defaults = values
+                .permissions
+                .iter()
+                .flat_map(|permissions| permissions.values())
+                .filter_map(|permission| permission.default.as_ref())
+                .chain(&values.default_permission);
+            for default in defaults {
+                check(
+                    &mut problems,
+                    default,
+                    str::parse::<spigot::DefaultPermission>,
+                );
+            }
+        }
+
+        if file == PAPER_PLUGIN_CONFIG {
+            let loads = values
+                .dependencies
+                .iter()
+                .flat_map(|dependencies| [&dependencies.bootstrap, &dependencies.server])
+                .flatten()
+                .flat_map(|dependencies| dependencies.values())
+                .filter_map(|dependency| dependency.load.as_ref());
+            for load in loads {
+                check(&mut problems, load, str::parse::<paper::LoadOrder>);
+            }
+        }
+
+        problems
+            .into_iter()
+            .min_by_key(|(_, location)| (location.is_none(), *location))
+    }
+}
+
+/// Records the problem `parse` finds with `value`, if any
+fn check<T>(
+    problems: &mut Vec<Problem>,
+    value: &Spanned<String>,
+    parse: impl FnOnce(&str) -> Result<T, PluginConfigErrorKind>,
+) {
+    if let Err(kind) = parse(&value.value) {
+        problems.push((kind, YamlLocation::from_saphyr(value.referenced)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{paper::PaperPluginYml, spigot::SpigotPluginYml};
+
+    fn spigot_error(yaml: &str) -> PluginConfigError {
+        let error = SpigotPluginYml::from_str(yaml).unwrap_err();
+        PluginConfigError::new(SPIGOT_PLUGIN_CONFIG, yaml, error)
+    }
+
+    #[test]
+    fn missing_field() {
+        let error = spigot_error("name: Test\nversion: 1.0\n");
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::MissingField {
+                field: "main".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_type() {
+        let error = spigot_error("name: Test\nversion: 1.0\nmain: a.B\ndepend: Other\n");
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::InvalidType {
+                detail: "invalid type, expected sequence".to_string()
+            }
+        );
+        assert_eq!(error.location, Some(YamlLocation { line: 4, column: 9 }));
+    }
+
+    #[test]
+    fn unknown_load_order() {
+        let error = spigot_error("name: Test\nversion: 1.0\nmain: a.B\nload: LATER\n");
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::UnknownLoadOrder {
+                value: "LATER".to_string()
+            }
+        );
+        assert_eq!(error.location, Some(YamlLocation { line: 4, column: 7 }));
+    }
+
+    #[test]
+    fn unknown_dependency_load_order() {
+        let yaml = "name: Test\nversion: 1.0\nmain: a.B\ndependencies:\n  server:\n    Other:\n      load: FIRST\n";
+        let error = PaperPluginYml::from_str(yaml).unwrap_err();
+        let error = PluginConfigError::new(PAPER_PLUGIN_CONFIG, yaml, error);
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::UnknownLoadOrder {
+                value: "FIRST".to_string()
+            }
+        );
+        assert_eq!(
+            error.location,
+            Some(YamlLocation {
+                line: 7,
+                column: 13
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_permission_default() {
+        // `maybe` also appears earlier in the file, the location is the one of the default
+        let yaml = "name: Test\nversion: 1.0\nmain: a.B\ndescription: maybe\npermissions:\n  test.use:\n    default: maybe\n";
+        let error = spigot_error(yaml);
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::InvalidPermissionDefault {
+                value: "maybe".to_string()
+            }
+        );
+        assert_eq!(
+            error.location,
+            Some(YamlLocation {
+                line: 7,
+                column: 14
+            })
+        );
+    }
+
+    #[test]
+    fn first_problem_in_the_file() {
+        let yaml = "name: Test\nversion: 1.0\npermissions:\n  test.use:\n    default: maybe\nload: LATER\n";
+        let error = spigot_error(yaml);
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::InvalidPermissionDefault {
+                value: "maybe".to_string()
+            }
+        );
+        assert_eq!(
+            error.location,
+            Some(YamlLocation {
+                line: 5,
+                column: 14
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_plugin_name() {
+        let error = spigot_error("name: ../Test\nversion: 1.0\nmain: a.B\n");
+        assert!(matches!(
+            error.kind,
+            PluginConfigErrorKind::InvalidPluginName { ref name, .. } if name == "../Test"
+        ));
+        assert_eq!(error.location, Some(YamlLocation { line: 1, column: 7 }));
+    }
+
+    #[test]
+    fn syntax() {
+        let error = spigot_error("name: Test\nversion: 1.0\nmain: a.B\ndepend: [Other\n");
+        assert_eq!(
+            error.kind,
+            PluginConfigErrorKind::Syntax {
+                message: "unclosed bracket '['".to_string()
+            }
+        );
+        assert_eq!(error.location, Some(YamlLocation { line: 4, column: 9 }));
+    }
+}
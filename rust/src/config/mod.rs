@@ -1,2 +1,5 @@
+pub mod error;
 pub mod paper;
+pub mod patchbukkit;
 pub mod spigot;
+pub mod version;
@@ -1,11 +1,12 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, de};
+use std::{collections::HashMap, str::FromStr};
+
+use crate::config::{error::PluginConfigErrorKind, spigot::deserialize_plugin_name};
 
 pub const PAPER_PLUGIN_CONFIG: &str = "paper-plugin.yml";
 
 /// Represents the load order for a dependency
-#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
-#[serde(rename_all = "UPPERCASE")]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub enum LoadOrder {
     /// Load the dependency before this plugin
     Before,
@@ -16,6 +17,29 @@ pub enum LoadOrder {
     Omit,
 }
 
+impl FromStr for LoadOrder {
+    type Err = PluginConfigErrorKind;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "BEFORE" => Ok(LoadOrder::Before),
+            "AFTER" => Ok(LoadOrder::After),
+            "OMIT" => Ok(LoadOrder::Omit),
+            _ => Err(PluginConfigErrorKind::UnknownLoadOrder {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LoadOrder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 /// Represents a single dependency declaration in Paper plugins
 #[derive(Debug, Deserialize, Clone)]
 pub struct PaperDependency {
@@ -34,6 +58,11 @@ pub struct PaperDependency {
     #[serde(rename = "join-classpath")]
     #[serde(default = "default_true")]
     pub join_classpath: bool,
+
+    /// Versions of the dependency this plugin works with (e.g. ">=1.7 <2.0")
+    /// Defaults to any version
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 fn default_true() -> bool {
@@ -46,6 +75,7 @@ impl Default for PaperDependency {
             load: LoadOrder::Omit,
             required: true,
             join_classpath: true,
+            version: None,
         }
     }
 }
@@ -127,6 +157,7 @@ impl PaperDependencies {
 pub struct PaperPluginYml {
     // Required fields
     /// The name of your plugin
+    #[serde(deserialize_with = "deserialize_plugin_name")]
     pub name: String,
 
     /// The current version of the plugin
@@ -171,6 +202,10 @@ pub struct PaperPluginYml {
     #[serde(default)]
     pub loader: Option<String>,
 
+    /// When to load the plugin (STARTUP or POSTWORLD)
+    #[serde(default)]
+    pub load: Option<crate::config::spigot::LoadOrder>,
+
     // Dependencies (Paper plugin style)
     /// Dependencies split into bootstrap and server sections
     #[serde(default)]
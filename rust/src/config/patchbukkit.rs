@@ -0,0 +1,293 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+pub const PATCHBUKKIT_CONFIG: &str = "patchbukkit.yml";
+
+const DEFAULT_CONFIG: &str = r#"# PatchBukkit configuration
+
+commands:
+  # Milliseconds to wait for a plugin's tab completions before answering with none, 0 waits forever
+  tab-complete-timeout-ms: 250
+  # Milliseconds tab completions are reused for the same plugin and input
+  tab-complete-cache-ms: 1000
+
+plugins:
+  # How many folders below patchbukkit-plugins/ are searched for jars, 0 only searches the folder itself.
+  # The update/ staging folder and the patchbukkit-libs/ library cache are never searched.
+  scan-depth: 0
+  # Glob patterns of jars to ignore, relative to patchbukkit-plugins/, e.g. `old/*.jar`
+  ignore: []
+  # Names of plugins that are never loaded
+  disabled-plugins: []
+  # When several jars declare the same plugin name, the one with the highest version is loaded.
+  # Map a plugin name to a jar file name to load that jar instead, e.g. `MyPlugin: MyPlugin-dev.jar`
+  prefer-jars: {}
+
+watchdog:
+  # Seconds the JVM worker may spend on one request before the Java threads are dumped to the log, 0 disables the watchdog
+  timeout-seconds: 30
+  # Interrupt a plugin that blocks the JVM worker and disable it once it returns
+  disable-stuck-plugins: false
+
+channel:
+  # Milliseconds to wait for the JVM worker per kind of request before giving up, 0 waits forever
+  timeouts:
+    # Loading, enabling, disabling and reloading plugins
+    lifecycle-ms: 300000
+    # Bukkit events, the event goes ahead uncancelled when a plugin is too slow
+    event-ms: 5000
+    # Commands run by players or the console
+    command-ms: 10000
+    # /patchbukkit queries
+    query-ms: 5000
+  # What happens to an event when the JVM command queue is full:
+  # block waits for room, drop skips the Java plugins, fail-open hands it over later without waiting for the outcome
+  event-overflow: block
+  # Warn when this many requests are waiting for the JVM worker, 0 never warns
+  queue-warn-depth: 80
+
+jvm:
+  # Does not choose the Java runtime: the JVM is always loaded from JAVA_HOME (or the java on the PATH).
+  # When set, PatchBukkit refuses to start unless JAVA_HOME points to this Java installation
+  java-home:
+  # Maximum and initial heap size, passed as -Xmx and -Xms (e.g. 2G). Unset keeps the JVM default
+  max-heap:
+  initial-heap:
+  # Garbage collector: G1, ZGC, Shenandoah, Parallel or Serial. Unset keeps the JVM default
+  gc:
+  # System properties, passed as -Dname=value
+  system-properties: {}
+  # Java agents such as profilers, a jar path optionally followed by =options, passed as -javaagent:
+  agents: []
+  # Any other JVM options, passed as written
+  extra-options: []
+"#;
+
+/// The PatchBukkit configuration, read from `patchbukkit.yml` in the PatchBukkit data folder
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct PatchBukkitConfig {
+    /// Settings for commands bridged from Java plugins
+    pub commands: CommandsConfig,
+    /// Settings for discovering and loading plugin jars
+    pub plugins: PluginsConfig,
+    /// Detection of plugins that block the JVM worker
+    pub watchdog: WatchdogConfig,
+    /// Timeouts and back-pressure for requests to the JVM worker
+    pub channel: ChannelConfig,
+    /// Options the JVM is started with
+    pub jvm: JvmConfig,
+}
+
+/// The `commands` section
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CommandsConfig {
+    /// Milliseconds to wait for tab completions before answering with none
+    pub tab_complete_timeout_ms: u64,
+    /// Milliseconds completions are cached per plugin and input
+    pub tab_complete_cache_ms: u64,
+}
+
+/// The `plugins` section
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PluginsConfig {
+    /// Folders below the plugin folder searched for jars
+    pub scan_depth: usize,
+    /// Glob patterns, relative to the plugin folder, of jars that are not loaded
+    pub ignore: Vec<String>,
+    /// Names of plugins that are not loaded
+    pub disabled_plugins: Vec<String>,
+    /// Jar file name to load, by plugin name, when several jars declare that plugin
+    pub prefer_jars: HashMap<String, String>,
+}
+
+/// The `watchdog` section
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WatchdogConfig {
+    /// Seconds one request may run on the JVM worker before it is reported, 0 to disable
+    pub timeout_seconds: u64,
+    /// Interrupt and disable the plugin that was running when the timeout was reached
+    pub disable_stuck_plugins: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 30,
+            disable_stuck_plugins: false,
+        }
+    }
+}
+
+/// The `channel` section
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ChannelConfig {
+    pub timeouts: ChannelTimeouts,
+    /// What happens to an event when the command queue is full
+    pub event_overflow: EventOverflow,
+    /// Queue depth at which a warning is logged, 0 to never warn
+    pub queue_warn_depth: usize,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            timeouts: ChannelTimeouts::default(),
+            event_overflow: EventOverflow::default(),
+            queue_warn_depth: 80,
+        }
+    }
+}
+
+/// Milliseconds to wait for the JVM worker per kind of request, 0 waits forever
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ChannelTimeouts {
+    pub lifecycle_ms: u64,
+    pub event_ms: u64,
+    pub command_ms: u64,
+    pub query_ms: u64,
+}
+
+impl Default for ChannelTimeouts {
+    fn default() -> Self {
+        Self {
+            lifecycle_ms: 300_000,
+            event_ms: 5_000,
+            command_ms: 10_000,
+            query_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventOverflow {
+    /// Wait for room in the queue, within the event timeout
+    #[default]
+    Block,
+    /// Skip the Java plugins for this event
+    Drop,
+    /// Let the event go ahead without its outcome and hand it to the Java plugins once there
+    /// is room, in order, dropping it when too many events are already waiting
+    FailOpen,
+}
+
+/// The `jvm` section
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct JvmConfig {
+    /// Java installation `JAVA_HOME` must point to, only checked since the JVM is always loaded
+    /// from `JAVA_HOME`
+    pub java_home: Option<PathBuf>,
+    pub max_heap: Option<String>,
+    pub initial_heap: Option<String>,
+    pub gc: Option<String>,
+    pub system_properties: BTreeMap<String, String>,
+    pub agents: Vec<String>,
+    pub extra_options: Vec<String>,
+}
+
+impl JvmConfig {
+    /// The JVM options described by this section, in a stable order.
+    ///
+    /// An unknown garbage collector is reported and left out.
+    pub fn java_options(&self) -> Vec<String> {
+        fn set(value: &Option<String>) -> Option<&str> {
+            value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+        }
+
+        let mut options = Vec::new();
+        if let Some(max_heap) = set(&self.max_heap) {
+            options.push(format!("-Xmx{}", max_heap));
+        }
+        if let Some(initial_heap) = set(&self.initial_heap) {
+            options.push(format!("-Xms{}", initial_heap));
+        }
+        if let Some(gc) = set(&self.gc) {
+            let flag = match gc.to_lowercase().as_str() {
+                "g1" | "g1gc" => Some("-XX:+UseG1GC"),
+                "z" | "zgc" => Some("-XX:+UseZGC"),
+                "shenandoah" | "shenandoahgc" => Some("-XX:+UseShenandoahGC"),
+                "parallel" | "parallelgc" => Some("-XX:+UseParallelGC"),
+                "serial" | "serialgc" => Some("-XX:+UseSerialGC"),
+                _ => None,
+            };
+            match flag {
+                Some(flag) => options.push(flag.to_string()),
+                None => log::warn!(
+                    "Unknown garbage collector `{}` in jvm.gc, keeping the JVM default",
+                    gc
+                ),
+            }
+        }
+        for (name, value) in &self.system_properties {
+            options.push(format!("-D{}={}", name, value));
+        }
+        for agent in &self.agents {
+            options.push(format!("-javaagent:{}", agent));
+        }
+        options.extend(self.extra_options.iter().cloned());
+        options
+    }
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            tab_complete_timeout_ms: 250,
+            tab_complete_cache_ms: 1000,
+        }
+    }
+}
+
+impl PatchBukkitConfig {
+    /// Parse a patchbukkit.yml from a YAML string
+    pub fn from_str(yaml: &str) -> Result<Self, serde_saphyr::Error> {
+        serde_saphyr::from_str(yaml)
+    }
+
+    /// Load the configuration from the data folder, writing the defaults when it does not exist.
+    ///
+    /// An unreadable or invalid file is reported and replaced by the defaults for this run.
+    pub fn load(data_folder: &Path) -> Self {
+        let path = data_folder.join(PATCHBUKKIT_CONFIG);
+        if !path.exists() {
+            if let Err(e) = fs::write(&path, DEFAULT_CONFIG) {
+                log::warn!("Failed to write default {}: {}", path.display(), e);
+            }
+            return Self::default();
+        }
+
+        let config = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| Self::from_str(&content).map_err(|e| e.to_string()));
+        match config {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Failed to load {}, using defaults: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_matches_defaults() {
+        let config = PatchBukkitConfig::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config, PatchBukkitConfig::default());
+    }
+}
@@ -1,37 +1,141 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
-use serde::Deserialize;
+use pumpkin_util::permission::{PermissionDefault, PermissionLvl};
+use serde::{Deserialize, Deserializer, de};
+
+use crate::config::error::PluginConfigErrorKind;
 
 pub const SPIGOT_PLUGIN_CONFIG: &str = "plugin.yml";
 
+/// Folders of the plugin folder PatchBukkit uses itself, which no plugin may be named after
+const RESERVED_PLUGIN_NAMES: [&str; 2] = ["update", "patchbukkit-libs"];
+
+/// Deserializes a plugin `name`, see `parse_plugin_name`
+pub fn deserialize_plugin_name<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    parse_plugin_name(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+/// Checks a plugin `name`, which also names the plugin's data folder.
+///
+/// Like Bukkit, only letters, digits, spaces, `_`, `.` and `-` are allowed and spaces are
+/// replaced with `_`. Names that would point outside the plugin's own folder or at one of
+/// PatchBukkit's folders are rejected.
+pub fn parse_plugin_name(raw_name: &str) -> Result<String, PluginConfigErrorKind> {
+    let name = raw_name.replace(' ', "_");
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, ' ' | '_' | '.' | '-');
+    let reason = if raw_name.is_empty() || !raw_name.chars().all(allowed) {
+        "only letters, digits, spaces, `_`, `.` and `-` are allowed"
+    } else if name == "."
+        || name == ".."
+        || RESERVED_PLUGIN_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(&name))
+    {
+        "it is reserved"
+    } else {
+        return Ok(name);
+    };
+    Err(PluginConfigErrorKind::InvalidPluginName {
+        name: raw_name.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
 /// Represents when a plugin should be loaded
-#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
-#[serde(rename_all = "UPPERCASE")]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub enum LoadOrder {
-    #[serde(alias = "startup", alias = "Startup")]
     Startup,
     #[default]
-    #[serde(alias = "postworld", alias = "Postworld")]
     Postworld,
 }
 
+impl FromStr for LoadOrder {
+    type Err = PluginConfigErrorKind;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "STARTUP" | "startup" | "Startup" => Ok(LoadOrder::Startup),
+            "POSTWORLD" | "postworld" | "Postworld" => Ok(LoadOrder::Postworld),
+            _ => Err(PluginConfigErrorKind::UnknownLoadOrder {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LoadOrder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 /// Represents the default permission value
-#[derive(Debug, Deserialize, Clone, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DefaultPermission {
     Op,
-    #[serde(alias = "notop")]
     NotOp,
     True,
     False,
 }
 
+impl FromStr for DefaultPermission {
+    type Err = PluginConfigErrorKind;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "op" => Ok(DefaultPermission::Op),
+            "notop" => Ok(DefaultPermission::NotOp),
+            "true" => Ok(DefaultPermission::True),
+            "false" => Ok(DefaultPermission::False),
+            _ => Err(PluginConfigErrorKind::InvalidPermissionDefault {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DefaultPermission {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 impl Default for DefaultPermission {
     fn default() -> Self {
         DefaultPermission::Op
     }
 }
 
+impl DefaultPermission {
+    /// Convert to a Pumpkin permission default, `op` granting the given permission level
+    pub fn to_permission_default(&self, op_level: PermissionLvl) -> PermissionDefault {
+        match self {
+            DefaultPermission::Op => PermissionDefault::Op(op_level),
+            DefaultPermission::True => PermissionDefault::Allow,
+            // Pumpkin cannot withhold a permission from operators only, so `notop` grants it
+            // to nobody rather than to everyone, see `warn_unsupported`
+            DefaultPermission::NotOp | DefaultPermission::False => PermissionDefault::Deny,
+        }
+    }
+
+    /// Warns when `node` uses a default Pumpkin cannot express, which is denied instead
+    pub fn warn_unsupported(&self, plugin: &str, node: &str) {
+        if matches!(self, DefaultPermission::NotOp) {
+            log::warn!(
+                "Permission {} of plugin {} defaults to notop, which Pumpkin does not support; it is denied to everyone until granted explicitly",
+                node,
+                plugin
+            );
+        }
+    }
+}
+
 /// Represents a permission node definition
 #[derive(Debug, Deserialize, Clone)]
 pub struct Permission {
@@ -82,6 +186,7 @@ impl StringOrList {
 pub struct SpigotPluginYml {
     // Required fields
     /// The name of your plugin
+    #[serde(deserialize_with = "deserialize_plugin_name")]
     pub name: String,
     /// The current version of the plugin
     pub version: String,
@@ -177,6 +282,17 @@ impl SpigotPluginYml {
         result
     }
 
+    /// Get the declared permission node, ignoring the case of its name
+    pub fn get_permission(&self, node: &str) -> Option<&Permission> {
+        let permissions = self.permissions.as_ref()?;
+        permissions.get(node).or_else(|| {
+            permissions
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(node))
+                .map(|(_, permission)| permission)
+        })
+    }
+
     /// Check if this plugin depends on another plugin
     pub fn depends_on(&self, plugin_name: &str) -> bool {
         if let Some(ref deps) = self.depend {
@@ -193,3 +309,15 @@ impl SpigotPluginYml {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_name_spaces_become_underscores() {
+        let plugin =
+            SpigotPluginYml::from_str("name: My Plugin\nversion: 1.0\nmain: a.B\n").unwrap();
+        assert_eq!(plugin.name, "My_Plugin");
+    }
+}
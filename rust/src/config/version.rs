@@ -0,0 +1,241 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::anyhow;
+
+/// The `paper-api` version bundled with PatchBukkit, set by build.rs
+pub const BUNDLED_API_VERSION: &str = env!("PATCHBUKKIT_PAPER_API_VERSION");
+
+/// A dotted numeric version such as `1.21.11`.
+///
+/// Parsing stops at the first non numeric part, so `1.21.11-R0.1-SNAPSHOT` and
+/// `2.3.0-beta` read as `1.21.11` and `2.3.0`. Missing parts compare as zero.
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let mut parts = Vec::new();
+        for part in version.split('.') {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if digits.is_empty() {
+                break;
+            }
+            parts.push(digits.parse().ok()?);
+            if digits.len() != part.len() {
+                break;
+            }
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(Self(parts))
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| {
+                let left = self.0.get(i).copied().unwrap_or(0);
+                let right = other.0.get(i).copied().unwrap_or(0);
+                left.cmp(&right)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparator {
+    Exact,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+/// A version requirement on a dependency, such as `>=1.7`, `>=2.0 <3.0` or `1.4.2`.
+///
+/// Comparators are separated by spaces or commas and must all match.
+/// A bare version requires exactly that version.
+#[derive(Debug, Clone)]
+pub struct VersionRange {
+    source: String,
+    constraints: Vec<(Comparator, Version)>,
+}
+
+impl VersionRange {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.constraints
+            .iter()
+            .all(|(comparator, bound)| match comparator {
+                Comparator::Exact => version == bound,
+                Comparator::Greater => version > bound,
+                Comparator::GreaterOrEqual => version >= bound,
+                Comparator::Less => version < bound,
+                Comparator::LessOrEqual => version <= bound,
+            })
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = anyhow::Error;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let mut constraints = Vec::new();
+        for constraint in range
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|constraint| !constraint.is_empty())
+        {
+            let (comparator, version) = [
+                (">=", Comparator::GreaterOrEqual),
+                ("<=", Comparator::LessOrEqual),
+                (">", Comparator::Greater),
+                ("<", Comparator::Less),
+                ("=", Comparator::Exact),
+            ]
+            .into_iter()
+            .find_map(|(prefix, comparator)| {
+                constraint
+                    .strip_prefix(prefix)
+                    .map(|version| (comparator, version))
+            })
+            .unwrap_or((Comparator::Exact, constraint));
+
+            let version = Version::parse(version)
+                .ok_or_else(|| anyhow!("Invalid version `{}` in range `{}`", version, range))?;
+            constraints.push((comparator, version));
+        }
+
+        if constraints.is_empty() {
+            return Err(anyhow!("Empty version range"));
+        }
+        Ok(Self {
+            source: range.trim().to_string(),
+            constraints,
+        })
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A plugin built against a newer API than the one PatchBukkit bundles
+#[derive(Debug)]
+pub struct IncompatibleApiVersion {
+    pub plugin: String,
+    pub api_version: String,
+}
+
+impl fmt::Display for IncompatibleApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requires api-version {}, but PatchBukkit bundles paper-api {}",
+            self.plugin, self.api_version, BUNDLED_API_VERSION
+        )
+    }
+}
+
+impl std::error::Error for IncompatibleApiVersion {}
+
+/// Refuses plugins whose `api-version` is newer than the bundled paper-api.
+///
+/// Plugins without an `api-version` are legacy plugins and always pass.
+pub fn check_api_version(
+    plugin: &str,
+    api_version: Option<&str>,
+) -> Result<(), IncompatibleApiVersion> {
+    let Some(api_version) = api_version else {
+        return Ok(());
+    };
+    let bundled = Version::parse(BUNDLED_API_VERSION).expect("bundled API version is valid");
+    match Version::parse(api_version) {
+        Some(version) if version <= bundled => Ok(()),
+        Some(_) => Err(IncompatibleApiVersion {
+            plugin: plugin.to_string(),
+            api_version: api_version.to_string(),
+        }),
+        None => {
+            log::warn!(
+                "{} declares an unreadable api-version `{}`, loading it anyway",
+                plugin,
+                api_version
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn range(range: &str) -> VersionRange {
+        range.parse().unwrap()
+    }
+
+    #[test]
+    fn pre_release_suffixes_are_ignored() {
+        assert_eq!(version("1.21.11-R0.1-SNAPSHOT"), version("1.21.11"));
+        assert_eq!(version("2.3.0-beta"), version("2.3"));
+        assert_eq!(version("v1.4.2+build.7"), version("1.4.2"));
+        assert!(Version::parse("beta").is_none());
+    }
+
+    #[test]
+    fn missing_parts_compare_as_zero() {
+        assert_eq!(version("1.21"), version("1.21.0"));
+        assert!(version("1.21") < version("1.21.1"));
+        assert!(version("2") > version("1.99.99"));
+    }
+
+    #[test]
+    fn combined_ranges_must_all_match() {
+        let between = range(">=1.7 <2.0");
+        assert!(between.matches(&version("1.7")));
+        assert!(between.matches(&version("1.9.4-SNAPSHOT")));
+        assert!(!between.matches(&version("2.0")));
+        assert!(!between.matches(&version("1.6.9")));
+
+        let commas = range(">1.0,<=1.5");
+        assert!(!commas.matches(&version("1.0")));
+        assert!(commas.matches(&version("1.5")));
+
+        assert!(range("1.4.2").matches(&version("1.4.2.0")));
+        assert!("".parse::<VersionRange>().is_err());
+        assert!(">=x".parse::<VersionRange>().is_err());
+    }
+
+    #[test]
+    fn api_versions_up_to_the_bundled_one_pass() {
+        assert!(check_api_version("Test", None).is_ok());
+        assert!(check_api_version("Test", Some("1.13")).is_ok());
+        assert!(check_api_version("Test", Some(BUNDLED_API_VERSION)).is_ok());
+        assert!(check_api_version("Test", Some("99.0")).is_err());
+    }
+}
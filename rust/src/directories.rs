@@ -25,6 +25,9 @@ pub fn setup_directories(server: &Context) -> Result<PatchBukkitDirectories, Str
     fs::create_dir_all(&plugins)
         .map_err(|err| format!("Failed to create patchbukkit-plugins folder: {:?}", err))?;
 
+    fs::create_dir_all(&plugin_updates)
+        .map_err(|err| format!("Failed to create plugin update folder: {:?}", err))?;
+
     Ok(PatchBukkitDirectories {
         base,
         plugins,
@@ -1,13 +1,18 @@
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use pumpkin::entity::player::Player;
 use pumpkin::plugin::{BoxFuture, Cancellable, EventHandler, Payload};
 use pumpkin::server::Server;
 use pumpkin_api_macros::with_runtime;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc;
 
-use crate::java::jvm::commands::JvmCommand;
+use crate::java::jvm::{
+    channel::{JvmCallError, fire_event},
+    commands::JvmCommand,
+    watchdog,
+};
 
 #[derive(Clone)]
 pub enum PatchBukkitEvent {
@@ -32,9 +37,36 @@ impl IntoEventData for pumpkin::plugin::player::player_join::PlayerJoinEvent {
     }
 }
 
+/// A Pumpkin listener registered on behalf of a Java plugin.
+///
+/// Pumpkin cannot remove a registered handler, so unregistering leaves the handler in
+/// place but stops it from forwarding events to the JVM. An unregistered handler is
+/// activated again when its plugin registers the same listener after a reload, instead of
+/// adding another handler to Pumpkin.
+#[derive(Clone)]
+pub struct ListenerHandle {
+    /// Fully qualified Bukkit event class
+    pub event_type: String,
+    active: Arc<AtomicBool>,
+}
+
+impl ListenerHandle {
+    pub fn unregister(&self) {
+        self.active.store(false, Ordering::Release);
+    }
+
+    /// Makes an unregistered handler forward events again, false if it was still active
+    pub fn reactivate(&self) -> bool {
+        self.active
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+}
+
 pub struct PatchBukkitEventHandler<E: IntoEventData> {
     plugin_name: String,
     command_tx: mpsc::Sender<JvmCommand>,
+    active: Arc<AtomicBool>,
     _phantom: PhantomData<E>,
 }
 
@@ -43,9 +75,17 @@ impl<E: IntoEventData> PatchBukkitEventHandler<E> {
         Self {
             plugin_name,
             command_tx,
+            active: Arc::new(AtomicBool::new(true)),
             _phantom: PhantomData,
         }
     }
+
+    pub fn handle(&self, event_type: impl Into<String>) -> ListenerHandle {
+        ListenerHandle {
+            event_type: event_type.into(),
+            active: self.active.clone(),
+        }
+    }
 }
 
 #[with_runtime(global)]
@@ -61,28 +101,33 @@ where
         let command_tx = self.command_tx.clone();
 
         Box::pin(async move {
-            let (tx, rx) = oneshot::channel();
-            if let Err(e) = command_tx
-                .send(JvmCommand::FireEvent {
-                    patchbukkit_event: event.into_patch_bukkit_event(server.clone()),
-                    respond_to: tx,
-                    plugin: self.plugin_name.clone(),
-                })
-                .await
-            {
-                log::error!("Failed to send event to JVM worker: {}", e);
+            // Plugins caught blocking the JVM worker get no events until they are disabled
+            if !self.active.load(Ordering::Acquire) || watchdog::is_stuck(&self.plugin_name) {
                 return;
             }
 
-            match rx.await {
-                Ok(cancelled) => {
-                    if cancelled {
-                        log::debug!("Event was cancelled by a Java plugin");
-                        event.set_cancelled(true);
-                    }
+            let result = fire_event(&command_tx, |respond_to| JvmCommand::FireEvent {
+                patchbukkit_event: event.into_patch_bukkit_event(server.clone()),
+                respond_to,
+                plugin: self.plugin_name.clone(),
+            })
+            .await;
+
+            // Without an answer the event goes ahead as if no Java plugin had cancelled it
+            match result {
+                Ok(Some(true)) => {
+                    log::debug!("Event was cancelled by a Java plugin");
+                    event.set_cancelled(true);
+                }
+                Ok(Some(false) | None) => {}
+                Err(JvmCallError::QueueFull) => {
+                    log::debug!(
+                        "Dropped an event for {}, the JVM command queue is full",
+                        self.plugin_name
+                    );
                 }
-                Err(_) => {
-                    log::warn!("JVM worker dropped response channel for event");
+                Err(e) => {
+                    log::warn!("Event for {} was not handled: {}", self.plugin_name, e);
                 }
             }
         })
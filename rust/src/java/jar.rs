@@ -1,28 +1,106 @@
 use std::{
-    fs::File,
+    collections::HashMap,
+    fs::{self, File},
     io::Read,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
-use glob::glob;
+use glob::{Pattern, glob};
+use walkdir::WalkDir;
 use zip::ZipArchive;
 
-use crate::config::{paper::PAPER_PLUGIN_CONFIG, spigot::SPIGOT_PLUGIN_CONFIG};
+use crate::config::{
+    paper::{PAPER_PLUGIN_CONFIG, PaperPluginYml},
+    patchbukkit::PluginsConfig,
+    spigot::{SPIGOT_PLUGIN_CONFIG, SpigotPluginYml},
+};
 
-pub fn discover_jar_files(plugin_folder: &PathBuf) -> impl Iterator<Item = PathBuf> {
-    let pattern = format!("{}/**/*.jar", plugin_folder.display());
+const UPDATE_BACKUP_FOLDER: &str = "backups";
 
-    glob(&pattern)
-        .expect("Invalid glob pattern")
+/// Folders of the plugin folder that hold jars which are not plugins to load: the update
+/// staging folder and the library cache filled by the Java plugin loader
+const EXCLUDED_FOLDERS: [&str; 2] = ["update", "patchbukkit-libs"];
+
+/// Finds the plugin jars to load, leaving out ignored jars and disabled plugins
+pub fn discover_jar_files(plugin_folder: &PathBuf, config: &PluginsConfig) -> Vec<PathBuf> {
+    let disabled: Vec<String> = config
+        .disabled_plugins
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    if disabled.is_empty() {
+        return scan_jar_files(plugin_folder, config);
+    }
+
+    scan_jar_files(plugin_folder, config)
+        .into_iter()
+        .filter(|jar_path| match read_plugin_name_from_jar(jar_path) {
+            Ok(Some(name)) if disabled.contains(&name) => {
+                log::info!(
+                    "Not loading `{}`: {} is listed in disabled-plugins",
+                    jar_path.display(),
+                    name
+                );
+                false
+            }
+            // Unreadable jars are reported when they are loaded
+            _ => true,
+        })
+        .collect()
+}
+
+/// Lists the jars up to `scan-depth` folders below the plugin folder, in path order
+fn scan_jar_files(plugin_folder: &PathBuf, config: &PluginsConfig) -> Vec<PathBuf> {
+    let ignore: Vec<Pattern> = config
+        .ignore
+        .iter()
+        .filter_map(|pattern| {
+            Pattern::new(pattern)
+                .map_err(|e| log::warn!("Invalid ignore pattern `{}`: {}", pattern, e))
+                .ok()
+        })
+        .collect();
+
+    let mut jars: Vec<PathBuf> = WalkDir::new(plugin_folder)
+        .max_depth(config.scan_depth + 1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.depth() == 1
+                && entry.file_type().is_dir()
+                && EXCLUDED_FOLDERS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        })
         .filter_map(|entry| {
             entry
-                .map_err(|e| log::error!("Glob error: {:?}", e))
-                .ok()?
+                .map_err(|e| log::error!("Failed to scan plugin folder: {:?}", e))
+                .ok()
+        })
+        .filter(|entry| {
+            entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "jar")
+        })
+        .filter(|entry| {
+            let relative = entry
+                .path()
+                .strip_prefix(plugin_folder)
+                .unwrap_or(entry.path());
+            let ignored = ignore.iter().any(|pattern| pattern.matches_path(relative));
+            if ignored {
+                log::info!("Ignoring `{}`", entry.path().display());
+            }
+            !ignored
+        })
+        .filter_map(|entry| {
+            entry
+                .path()
                 .canonicalize()
                 .map_err(|e| log::error!("Canonicalize error: {:?}", e))
                 .ok()
         })
+        .collect();
+    jars.sort();
+    jars
 }
 
 pub fn read_configs_from_jar<P: AsRef<Path>>(
@@ -51,3 +129,303 @@ pub fn read_configs_from_jar<P: AsRef<Path>>(
 
     Ok((paper_plugin_yml, spigot_plugin_yml))
 }
+
+/// Reads the plugin name declared in a jar's `paper-plugin.yml` or `plugin.yml`.
+pub fn read_plugin_name_from_jar<P: AsRef<Path>>(jar_path: P) -> Result<Option<String>> {
+    let name = match read_configs_from_jar(jar_path)? {
+        (Some(paper), _) => Some(PaperPluginYml::from_str(&paper)?.name),
+        (None, Some(spigot)) => Some(SpigotPluginYml::from_str(&spigot)?.name),
+        (None, None) => None,
+    };
+    Ok(name.map(|name| name.trim().to_lowercase()))
+}
+
+/// Replaces installed plugin jars with the matching jars staged in the update folder.
+///
+/// Jars are matched by the plugin name in their configuration, the replaced jar is kept
+/// in `update/backups/`. Staged jars without an installed counterpart are left untouched.
+pub fn apply_plugin_updates(
+    plugin_folder: &PathBuf,
+    update_folder: &PathBuf,
+    config: &PluginsConfig,
+) {
+    let Ok(update_folder) = update_folder.canonicalize() else {
+        return;
+    };
+
+    let mut installed: HashMap<String, PathBuf> = HashMap::new();
+    for jar_path in scan_jar_files(plugin_folder, config) {
+        match read_plugin_name_from_jar(&jar_path) {
+            Ok(Some(name)) => {
+                installed.entry(name).or_insert(jar_path);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "Failed to read plugin name from `{}`: {}",
+                jar_path.display(),
+                e
+            ),
+        }
+    }
+
+    let pattern = format!("{}/*.jar", update_folder.display());
+    let staged_jars = glob(&pattern)
+        .expect("Invalid glob pattern")
+        .filter_map(|entry| entry.map_err(|e| log::error!("Glob error: {:?}", e)).ok());
+
+    for staged_jar in staged_jars {
+        let name = match read_plugin_name_from_jar(&staged_jar) {
+            Ok(Some(name)) => name,
+            Ok(None) => {
+                log::warn!(
+                    "Ignoring update `{}`: no plugin configuration file found",
+                    staged_jar.display()
+                );
+                continue;
+            }
+            Err(e) => {
+                log::error!(
+                    "Ignoring update `{}`: failed to read plugin configuration: {}",
+                    staged_jar.display(),
+                    e
+                );
+                continue;
+            }
+        };
+
+        let Some(installed_jar) = installed.get(&name) else {
+            log::warn!(
+                "Ignoring update `{}`: no installed plugin named `{}`",
+                staged_jar.display(),
+                name
+            );
+            continue;
+        };
+
+        match swap_plugin_jar(
+            installed_jar,
+            &staged_jar,
+            &update_folder.join(UPDATE_BACKUP_FOLDER),
+        ) {
+            Ok((target, backup)) => log::info!(
+                "Updated plugin `{}`: `{}` replaced by `{}` (backup at `{}`)",
+                name,
+                installed_jar.display(),
+                target.display(),
+                backup.display()
+            ),
+            Err(e) => log::error!(
+                "Failed to apply update `{}` for plugin `{}`: {}",
+                staged_jar.display(),
+                name,
+                e
+            ),
+        }
+    }
+}
+
+/// Replaces `old_jar` with `new_jar`, keeping a copy of `old_jar` in the backup folder.
+///
+/// The new jar keeps its own file name, so a different jar already using that name in the
+/// plugin folder stops the update instead of being overwritten. It is copied next to its
+/// destination first and renamed into place, so the plugin folder never holds a partially
+/// copied jar. When the file name is unchanged the rename replaces the old jar in one step;
+/// otherwise both jars are briefly present until the old one is removed. On failure the
+/// plugin folder is left as it was and the update stays in the update folder.
+fn swap_plugin_jar(
+    old_jar: &Path,
+    new_jar: &Path,
+    backup_folder: &Path,
+) -> Result<(PathBuf, PathBuf)> {
+    let old_file_name = old_jar
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid plugin jar path"))?;
+    let new_file_name = new_jar
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid update jar path"))?;
+
+    let target = old_jar.with_file_name(new_file_name);
+    if target != old_jar && target.exists() {
+        return Err(anyhow::anyhow!(
+            "`{}` already exists and is not the jar being updated",
+            target.display()
+        ));
+    }
+
+    fs::create_dir_all(backup_folder)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let backup = backup_folder.join(format!(
+        "{}.{}.bak",
+        old_file_name.to_string_lossy(),
+        timestamp
+    ));
+    fs::copy(old_jar, &backup)?;
+
+    let staged = target.with_extension("jar.part");
+    if let Err(e) = fs::copy(new_jar, &staged).and_then(|_| fs::rename(&staged, &target)) {
+        remove_leftover(&staged);
+        remove_leftover(&backup);
+        return Err(e.into());
+    }
+
+    if target != old_jar
+        && let Err(e) = fs::remove_file(old_jar)
+    {
+        // Two jars of the same plugin would be found on the next scan
+        remove_leftover(&target);
+        remove_leftover(&backup);
+        return Err(e.into());
+    }
+    remove_leftover(new_jar);
+
+    Ok((target, backup))
+}
+
+/// Removes a file left behind by `swap_plugin_jar`, logging when that fails
+fn remove_leftover(path: &Path) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::error!("Failed to remove `{}`: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty folder under the system temp folder, unique to `name`
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("patchbukkit-jar-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn file_names(folder: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn swap_same_name() {
+        let folder = temp_folder("swap-same-name");
+        let old_jar = folder.join("Test.jar");
+        let new_jar = folder.join("update/Test.jar");
+        write(&old_jar, "old");
+        write(&new_jar, "new");
+
+        let (target, backup) =
+            swap_plugin_jar(&old_jar, &new_jar, &folder.join("update/backups")).unwrap();
+        assert_eq!(target, old_jar);
+        assert_eq!(fs::read_to_string(&old_jar).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+        assert!(!new_jar.exists());
+        assert_eq!(file_names(&folder), ["Test.jar", "update"]);
+    }
+
+    #[test]
+    fn swap_new_name() {
+        let folder = temp_folder("swap-new-name");
+        let old_jar = folder.join("Test-1.0.jar");
+        let new_jar = folder.join("update/Test-2.0.jar");
+        write(&old_jar, "old");
+        write(&new_jar, "new");
+
+        let (target, backup) =
+            swap_plugin_jar(&old_jar, &new_jar, &folder.join("update/backups")).unwrap();
+        assert_eq!(target, folder.join("Test-2.0.jar"));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+        assert!(!new_jar.exists());
+        assert_eq!(file_names(&folder), ["Test-2.0.jar", "update"]);
+    }
+
+    #[test]
+    fn swap_refuses_to_overwrite_another_jar() {
+        let folder = temp_folder("swap-refuses");
+        let old_jar = folder.join("Test-1.0.jar");
+        let other_jar = folder.join("Test-2.0.jar");
+        let new_jar = folder.join("update/Test-2.0.jar");
+        write(&old_jar, "old");
+        write(&other_jar, "other");
+        write(&new_jar, "new");
+
+        assert!(swap_plugin_jar(&old_jar, &new_jar, &folder.join("update/backups")).is_err());
+        assert_eq!(fs::read_to_string(&old_jar).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&other_jar).unwrap(), "other");
+        assert_eq!(fs::read_to_string(&new_jar).unwrap(), "new");
+    }
+
+    #[test]
+    fn failed_backup_leaves_everything_in_place() {
+        let folder = temp_folder("swap-failed-backup");
+        let old_jar = folder.join("Test.jar");
+        let new_jar = folder.join("update/Test.jar");
+        write(&old_jar, "old");
+        write(&new_jar, "new");
+        // A file where the backup folder should be
+        let backup_folder = folder.join("update/backups");
+        write(&backup_folder, "");
+
+        assert!(swap_plugin_jar(&old_jar, &new_jar, &backup_folder).is_err());
+        assert_eq!(fs::read_to_string(&old_jar).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&new_jar).unwrap(), "new");
+        assert_eq!(file_names(&folder), ["Test.jar", "update"]);
+    }
+
+    #[test]
+    fn failed_install_leaves_no_staged_jar() {
+        let folder = temp_folder("swap-failed-install");
+        let old_jar = folder.join("Test.jar");
+        // The update vanished before it could be copied
+        let new_jar = folder.join("update/Test.jar");
+        let backup_folder = folder.join("update/backups");
+        write(&old_jar, "old");
+
+        assert!(swap_plugin_jar(&old_jar, &new_jar, &backup_folder).is_err());
+        assert_eq!(fs::read_to_string(&old_jar).unwrap(), "old");
+        assert_eq!(file_names(&folder), ["Test.jar", "update"]);
+        assert!(file_names(&backup_folder).is_empty());
+    }
+
+    #[test]
+    fn discover_skips_excluded_and_ignored_jars() {
+        let folder = temp_folder("discover");
+        for jar in [
+            "A.jar",
+            "nested/B.jar",
+            "nested/deeper/C.jar",
+            "old/D.jar",
+            "update/E.jar",
+            "patchbukkit-libs/F.jar",
+            "notes.txt",
+        ] {
+            write(&folder.join(jar), "");
+        }
+        let config = PluginsConfig {
+            scan_depth: 1,
+            ignore: vec!["old/*.jar".to_string()],
+            ..Default::default()
+        };
+
+        let folder = folder.canonicalize().unwrap();
+        assert_eq!(
+            discover_jar_files(&folder, &config),
+            [folder.join("A.jar"), folder.join("nested/B.jar")]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Mutex, RwLock,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    config::patchbukkit::{ChannelConfig, EventOverflow},
+    java::jvm::commands::JvmCommand,
+};
+
+/// Events the `fail-open` policy can hold back while the command queue is full, beyond
+/// which they are dropped
+const OVERFLOW_CAPACITY: usize = 256;
+
+static SETTINGS: RwLock<Option<ChannelConfig>> = RwLock::new(None);
+static METRICS: ChannelMetrics = ChannelMetrics::new();
+static OVERFLOW: Mutex<Option<OverflowBuffer>> = Mutex::new(None);
+
+/// A `fail-open` event waiting for room in the command queue, and a future waiting for its
+/// outcome, which keeps the worker from skipping it as abandoned
+type Overflowed = (JvmCommand, Pin<Box<dyn Future<Output = ()> + Send>>);
+
+/// Holds back `fail-open` events for one command queue, which a task feeds them to
+struct OverflowBuffer {
+    /// Weak, so the buffer does not keep the queue of a stopped worker open
+    command_tx: mpsc::WeakSender<JvmCommand>,
+    overflow_tx: mpsc::Sender<Overflowed>,
+}
+
+impl OverflowBuffer {
+    fn spawn(command_tx: &mpsc::Sender<JvmCommand>) -> Self {
+        let (overflow_tx, mut overflow_rx) = mpsc::channel::<Overflowed>(OVERFLOW_CAPACITY);
+        let feeder_tx = command_tx.downgrade();
+        tokio::spawn(async move {
+            while let Some((command, outcome)) = overflow_rx.recv().await {
+                let Some(command_tx) = feeder_tx.upgrade() else {
+                    break;
+                };
+                if command_tx.send(command).await.is_err() {
+                    break;
+                }
+                drop(command_tx);
+                // A stuck event holds up the ones behind it no longer than a direct one would
+                let _ = within(CommandKind::Event, timeout(CommandKind::Event), async {
+                    outcome.await;
+                    Ok(())
+                })
+                .await;
+            }
+        });
+        Self {
+            command_tx: command_tx.downgrade(),
+            overflow_tx,
+        }
+    }
+
+    /// Whether this buffer feeds `command_tx`, a restarted worker comes with a new queue
+    fn feeds(&self, command_tx: &mpsc::Sender<JvmCommand>) -> bool {
+        self.command_tx
+            .upgrade()
+            .is_some_and(|feeder_tx| feeder_tx.same_channel(command_tx))
+    }
+}
+
+/// The kinds of requests sent to the JVM worker, each with its own timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    /// Loading, bootstrapping, instantiating, enabling and disabling plugins
+    Lifecycle,
+    /// Bukkit events fired from Pumpkin listeners
+    Event,
+    /// Commands run by players or the console
+    Command,
+    /// Lookups such as `/patchbukkit list`
+    Query,
+    /// Tab completions, which wait for `commands.tab-complete-timeout-ms` instead
+    TabComplete,
+}
+
+impl fmt::Display for CommandKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CommandKind::Lifecycle => "lifecycle",
+            CommandKind::Event => "event",
+            CommandKind::Command => "command",
+            CommandKind::Query => "query",
+            CommandKind::TabComplete => "tab completion",
+        })
+    }
+}
+
+/// Why a request to the JVM worker got no answer
+#[derive(Debug)]
+pub enum JvmCallError {
+    /// The worker did not answer within the timeout of this kind of request
+    Timeout { kind: CommandKind, after: Duration },
+    /// The command queue was full and the event overflow policy is `drop`
+    QueueFull,
+    /// The worker thread is gone
+    WorkerGone,
+}
+
+impl fmt::Display for JvmCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JvmCallError::Timeout { kind, after } => write!(
+                f,
+                "The JVM worker did not answer a {} request within {}ms",
+                kind,
+                after.as_millis()
+            ),
+            JvmCallError::QueueFull => write!(f, "The JVM command queue is full"),
+            JvmCallError::WorkerGone => write!(f, "The JVM worker is not available"),
+        }
+    }
+}
+
+impl std::error::Error for JvmCallError {}
+
+/// Counters describing the JVM command queue
+pub struct ChannelMetrics {
+    high_water: AtomicUsize,
+    dropped_events: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+/// A snapshot of the command queue, for `/patchbukkit queue`
+#[derive(Debug, Clone)]
+pub struct ChannelStats {
+    pub depth: usize,
+    pub capacity: usize,
+    pub high_water: usize,
+    pub dropped_events: u64,
+    pub timeouts: u64,
+}
+
+impl ChannelMetrics {
+    const fn new() -> Self {
+        Self {
+            high_water: AtomicUsize::new(0),
+            dropped_events: AtomicU64::new(0),
+            timeouts: AtomicU64::new(0),
+        }
+    }
+
+    fn record_depth(&self, command_tx: &mpsc::Sender<JvmCommand>) {
+        let depth = queue_depth(command_tx);
+        let previous = self.high_water.fetch_max(depth, Ordering::Relaxed);
+        let warn_depth = settings().queue_warn_depth;
+        if warn_depth > 0 && depth >= warn_depth && previous < warn_depth {
+            log::warn!(
+                "{} requests are queued for the JVM worker (capacity {}), a plugin may be slow",
+                depth,
+                command_tx.max_capacity()
+            );
+        }
+    }
+}
+
+/// Applies the `channel` section of patchbukkit.yml, replacing the one applied before
+pub fn configure(config: &ChannelConfig) {
+    *SETTINGS.write().unwrap() = Some(config.clone());
+}
+
+fn settings() -> ChannelConfig {
+    SETTINGS.read().unwrap().clone().unwrap_or_default()
+}
+
+fn queue_depth(command_tx: &mpsc::Sender<JvmCommand>) -> usize {
+    command_tx.max_capacity() - command_tx.capacity()
+}
+
+pub fn stats(command_tx: &mpsc::Sender<JvmCommand>) -> ChannelStats {
+    ChannelStats {
+        depth: queue_depth(command_tx),
+        capacity: command_tx.max_capacity(),
+        high_water: METRICS.high_water.load(Ordering::Relaxed),
+        dropped_events: METRICS.dropped_events.load(Ordering::Relaxed),
+        timeouts: METRICS.timeouts.load(Ordering::Relaxed),
+    }
+}
+
+/// The timeout configured for `kind`
+fn timeout(kind: CommandKind) -> Duration {
+    let timeouts = settings().timeouts;
+    Duration::from_millis(match kind {
+        CommandKind::Lifecycle => timeouts.lifecycle_ms,
+        CommandKind::Event => timeouts.event_ms,
+        CommandKind::Command | CommandKind::TabComplete => timeouts.command_ms,
+        CommandKind::Query => timeouts.query_ms,
+    })
+}
+
+/// Runs `future` within `after`, no timeout when it is zero
+async fn within<T>(
+    kind: CommandKind,
+    after: Duration,
+    future: impl Future<Output = Result<T, JvmCallError>>,
+) -> Result<T, JvmCallError> {
+    if after.is_zero() {
+        return future.await;
+    }
+    match tokio::time::timeout(after, future).await {
+        Ok(result) => result,
+        Err(_) => {
+            METRICS.timeouts.fetch_add(1, Ordering::Relaxed);
+            Err(JvmCallError::Timeout { kind, after })
+        }
+    }
+}
+
+/// Sends a request to the JVM worker and waits for its answer, giving up after the
+/// timeout configured for `kind`
+pub async fn request<T>(
+    command_tx: &mpsc::Sender<JvmCommand>,
+    kind: CommandKind,
+    command: impl FnOnce(oneshot::Sender<T>) -> JvmCommand,
+) -> Result<T, JvmCallError> {
+    request_within(command_tx, kind, timeout(kind), command).await
+}
+
+/// Like `request`, giving up after `after` instead of the timeout configured for `kind`
+pub async fn request_within<T>(
+    command_tx: &mpsc::Sender<JvmCommand>,
+    kind: CommandKind,
+    after: Duration,
+    command: impl FnOnce(oneshot::Sender<T>) -> JvmCommand,
+) -> Result<T, JvmCallError> {
+    let (tx, rx) = oneshot::channel();
+    within(kind, after, async {
+        command_tx
+            .send(command(tx))
+            .await
+            .map_err(|_| JvmCallError::WorkerGone)?;
+        METRICS.record_depth(command_tx);
+        rx.await.map_err(|_| JvmCallError::WorkerGone)
+    })
+    .await
+}
+
+/// Hands a `fail-open` event to the overflow buffer, which feeds the command queue one
+/// event at a time and in the order they came in
+fn overflow<T: Send + 'static>(
+    command_tx: &mpsc::Sender<JvmCommand>,
+    command: JvmCommand,
+    outcome: oneshot::Receiver<T>,
+) -> Result<Option<T>, JvmCallError> {
+    let mut buffer = OVERFLOW.lock().unwrap();
+    let overflow_tx = match &mut *buffer {
+        Some(buffer) if buffer.feeds(command_tx) => &buffer.overflow_tx,
+        stale => &stale.insert(OverflowBuffer::spawn(command_tx)).overflow_tx,
+    };
+    let outcome = Box::pin(async move {
+        let _ = outcome.await;
+    });
+    match overflow_tx.try_send((command, outcome)) {
+        Ok(()) => Ok(None),
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            METRICS.dropped_events.fetch_add(1, Ordering::Relaxed);
+            Err(JvmCallError::QueueFull)
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => Err(JvmCallError::WorkerGone),
+    }
+}
+
+/// Whether `fail-open` events are waiting in the overflow buffer, newer events queue
+/// behind them to keep their order
+fn overflowing() -> bool {
+    OVERFLOW
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|buffer| buffer.overflow_tx.capacity() < buffer.overflow_tx.max_capacity())
+}
+
+/// Fires an event on the JVM worker, applying the event overflow policy when the queue
+/// is full.
+///
+/// Returns `Ok(None)` when the event was handed over without waiting for its outcome.
+pub async fn fire_event<T: Send + 'static>(
+    command_tx: &mpsc::Sender<JvmCommand>,
+    command: impl FnOnce(oneshot::Sender<T>) -> JvmCommand,
+) -> Result<Option<T>, JvmCallError> {
+    let (tx, rx) = oneshot::channel();
+    let overflow_policy = settings().event_overflow;
+    let permit = match overflow_policy {
+        EventOverflow::Block => None,
+        EventOverflow::FailOpen if overflowing() => return overflow(command_tx, command(tx), rx),
+        EventOverflow::Drop | EventOverflow::FailOpen => match command_tx.try_reserve() {
+            Ok(permit) => Some(permit),
+            Err(mpsc::error::TrySendError::Closed(_)) => return Err(JvmCallError::WorkerGone),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                if overflow_policy == EventOverflow::Drop {
+                    METRICS.dropped_events.fetch_add(1, Ordering::Relaxed);
+                    return Err(JvmCallError::QueueFull);
+                }
+                // Fail open: the event goes ahead untouched and reaches the Java plugins later
+                return overflow(command_tx, command(tx), rx);
+            }
+        },
+    };
+
+    let command = command(tx);
+    within(CommandKind::Event, timeout(CommandKind::Event), async {
+        match permit {
+            Some(permit) => permit.send(command),
+            None => command_tx
+                .send(command)
+                .await
+                .map_err(|_| JvmCallError::WorkerGone)?,
+        }
+        METRICS.record_depth(command_tx);
+        rx.await.map(Some).map_err(|_| JvmCallError::WorkerGone)
+    })
+    .await
+}
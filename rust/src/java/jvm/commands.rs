@@ -6,7 +6,18 @@ use pumpkin_protocol::java::client::play::CommandSuggestion;
 use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
-use crate::{commands::SimpleCommandSender, events::handler::PatchBukkitEvent};
+use crate::{
+    commands::{SimpleCommandSender, tab_complete::TabCompleteTicket},
+    config::{
+        error::PluginConfigError, patchbukkit::PatchBukkitConfig, spigot::LoadOrder,
+        version::IncompatibleApiVersion,
+    },
+    events::handler::PatchBukkitEvent,
+    java::plugin::{
+        dependency_report::DependencyReport,
+        manager::{DuplicatePlugin, PluginState},
+    },
+};
 
 pub enum LoadPluginResult {
     SuccessfullyLoadedSpigot,
@@ -15,6 +26,12 @@ pub enum LoadPluginResult {
     FailedToLoadPaperPlugin(anyhow::Error),
     FailedToReadConfigurationFile(anyhow::Error),
     NoConfigurationFile,
+    /// The plugin targets a newer `api-version` than the bundled paper-api
+    IncompatibleApiVersion(IncompatibleApiVersion),
+    /// The plugin.yml or paper-plugin.yml could not be parsed
+    InvalidConfiguration(PluginConfigError),
+    /// Another jar with the same plugin name was preferred
+    DuplicatePlugin(DuplicatePlugin),
 }
 
 pub enum JvmCommand {
@@ -23,22 +40,64 @@ pub enum JvmCommand {
         respond_to: oneshot::Sender<Result<()>>,
         context: Arc<Context>,
         command_tx: mpsc::Sender<JvmCommand>,
+        config: Arc<PatchBukkitConfig>,
     },
+    /// Apply the parts of patchbukkit.yml needed before plugins are loaded
+    Configure { config: Arc<PatchBukkitConfig> },
     LoadPlugin {
         plugin_path: PathBuf,
         respond_to: oneshot::Sender<LoadPluginResult>,
     },
+    /// Run the Paper loaders and bootstrappers, before any plugin is instantiated
+    BootstrapAllPlugins {
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     InstantiateAllPlugins {
         respond_to: oneshot::Sender<Result<()>>,
         server: Arc<Context>,
         command_tx: mpsc::Sender<JvmCommand>,
     },
+    /// The dependency problems found when the plugins were instantiated
+    GetDependencyReport {
+        respond_to: oneshot::Sender<DependencyReport>,
+    },
+    /// Enable the plugins of one `load` phase
     EnableAllPlugins {
+        load: LoadOrder,
         respond_to: oneshot::Sender<Result<()>>,
     },
     DisableAllPlugins {
         respond_to: oneshot::Sender<Result<()>>,
     },
+    /// Disable a plugin, read its jar again and enable the new copy
+    ReloadPlugin {
+        name: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Disable a plugin and release its jar
+    UnloadPlugin {
+        name: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    ListPlugins {
+        respond_to: oneshot::Sender<Vec<PluginInfo>>,
+    },
+    GetPluginInfo {
+        name: String,
+        respond_to: oneshot::Sender<Option<PluginInfo>>,
+    },
+    /// The Bukkit listeners of every instantiated plugin, by plugin name
+    ListEventListeners {
+        respond_to: oneshot::Sender<Result<Vec<(String, Vec<EventListenerInfo>)>>>,
+    },
+    EnablePlugin {
+        name: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    DisablePlugin {
+        name: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     Shutdown {
         respond_to: oneshot::Sender<Result<()>>,
     },
@@ -50,16 +109,114 @@ pub enum JvmCommand {
     TriggerCommand {
         full_command: String,
         command_sender: SimpleCommandSender,
-        respond_to: oneshot::Sender<Result<()>>,
+        respond_to: oneshot::Sender<Result<CommandOutcome>>,
     },
     GetCommandTabComplete {
         command_sender: SimpleCommandSender,
         full_command: String,
         respond_to: oneshot::Sender<Result<Option<Vec<CommandSuggestion>>, CommandError>>,
         location: Option<Location>,
+        ticket: TabCompleteTicket,
     },
 }
 
+impl JvmCommand {
+    /// A short description of the command for the watchdog log
+    pub fn describe(&self) -> String {
+        let name = match self {
+            JvmCommand::Initialize { .. } => "Initialize",
+            JvmCommand::Configure { .. } => "Configure",
+            JvmCommand::LoadPlugin { .. } => "LoadPlugin",
+            JvmCommand::BootstrapAllPlugins { .. } => "BootstrapAllPlugins",
+            JvmCommand::InstantiateAllPlugins { .. } => "InstantiateAllPlugins",
+            JvmCommand::GetDependencyReport { .. } => "GetDependencyReport",
+            JvmCommand::EnableAllPlugins { .. } => "EnableAllPlugins",
+            JvmCommand::DisableAllPlugins { .. } => "DisableAllPlugins",
+            JvmCommand::ReloadPlugin { .. } => "ReloadPlugin",
+            JvmCommand::UnloadPlugin { .. } => "UnloadPlugin",
+            JvmCommand::ListPlugins { .. } => "ListPlugins",
+            JvmCommand::GetPluginInfo { .. } => "GetPluginInfo",
+            JvmCommand::ListEventListeners { .. } => "ListEventListeners",
+            JvmCommand::EnablePlugin { .. } => "EnablePlugin",
+            JvmCommand::DisablePlugin { .. } => "DisablePlugin",
+            JvmCommand::Shutdown { .. } => "Shutdown",
+            JvmCommand::FireEvent { .. } => "FireEvent",
+            JvmCommand::TriggerCommand { full_command, .. } => {
+                return format!("TriggerCommand `{}`", full_command);
+            }
+            JvmCommand::GetCommandTabComplete { full_command, .. } => {
+                return format!("GetCommandTabComplete `{}`", full_command);
+            }
+        };
+        name.to_string()
+    }
+
+    /// Whether the caller stopped waiting for the answer, usually after its timeout.
+    ///
+    /// Only events, commands and queries are worthless once their caller gave up, lifecycle
+    /// requests always run.
+    pub fn is_abandoned(&self) -> bool {
+        match self {
+            JvmCommand::FireEvent { respond_to, .. } => respond_to.is_closed(),
+            JvmCommand::TriggerCommand { respond_to, .. } => respond_to.is_closed(),
+            JvmCommand::GetCommandTabComplete { respond_to, .. } => respond_to.is_closed(),
+            JvmCommand::ListPlugins { respond_to } => respond_to.is_closed(),
+            JvmCommand::GetPluginInfo { respond_to, .. } => respond_to.is_closed(),
+            JvmCommand::GetDependencyReport { respond_to } => respond_to.is_closed(),
+            JvmCommand::ListEventListeners { respond_to } => respond_to.is_closed(),
+            _ => false,
+        }
+    }
+
+    /// The plugin the command runs on behalf of, when it names one
+    pub fn plugin(&self) -> Option<&str> {
+        match self {
+            JvmCommand::FireEvent { plugin, .. } => Some(plugin),
+            JvmCommand::ReloadPlugin { name, .. }
+            | JvmCommand::UnloadPlugin { name, .. }
+            | JvmCommand::EnablePlugin { name, .. }
+            | JvmCommand::DisablePlugin { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// What the `/patchbukkit` command shows about a plugin
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub state: PluginState,
+    pub authors: Vec<String>,
+    pub depends: Vec<String>,
+    pub soft_depends: Vec<String>,
+    pub provides: Vec<String>,
+    pub commands: Vec<String>,
+    /// Fully qualified Bukkit event classes the plugin listens to through Pumpkin
+    pub events: Vec<String>,
+}
+
+/// A Bukkit listener registered by a plugin, for `/patchbukkit events`
+pub struct EventListenerInfo {
+    /// Fully qualified Bukkit event class
+    pub event: String,
+    pub priority: String,
+    pub ignore_cancelled: bool,
+    /// Fully qualified class of the listener
+    pub listener: String,
+    /// Whether a Pumpkin listener forwards the event to the plugin, other events only
+    /// reach it when fired from Java
+    pub forwarded: bool,
+}
+
+pub struct CommandOutcome {
+    /// Whether the command map knows the command's label
+    pub found: bool,
+    /// What the command map's `dispatch` returned, false when `onCommand` returned false
+    pub success: bool,
+    /// Messages captured from senders that cannot receive them directly, such as RCON
+    pub output: Vec<String>,
+}
+
 pub struct Rotation {
     pub yaw: f32,
     pub pitch: f32,
@@ -1,2 +1,5 @@
+pub mod channel;
 pub mod commands;
+pub mod runtime;
+pub mod watchdog;
 pub mod worker;
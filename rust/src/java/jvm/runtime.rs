@@ -0,0 +1,230 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::config::patchbukkit::JvmConfig;
+
+/// The oldest Java release PatchBukkit runs on, the FFM API it relies on needs it
+pub const MINIMUM_JAVA_VERSION: u32 = 25;
+
+/// Where `libjvm` lives below a Java home, per platform
+const LIBJVM_PATHS: [&str; 4] = [
+    "lib/server/libjvm.so",
+    "lib/server/libjvm.dylib",
+    "bin/server/jvm.dll",
+    "jre/lib/amd64/server/libjvm.so",
+];
+
+/// Folders holding one Java installation per subfolder
+const INSTALL_ROOTS: [&str; 6] = [
+    "/usr/lib/jvm",
+    "/usr/java",
+    "/opt/java",
+    "/Library/Java/JavaVirtualMachines",
+    "C:\\Program Files\\Java",
+    "C:\\Program Files\\Eclipse Adoptium",
+];
+
+/// The Java installation j4rs starts the JVM from
+#[derive(Debug)]
+pub struct JavaRuntime {
+    pub home: PathBuf,
+    /// The full version from the `release` file, e.g. `25.0.1`
+    pub version: String,
+    pub major: u32,
+}
+
+impl JavaRuntime {
+    /// Reads the Java installation at `home`, or `None` when it has no `libjvm`
+    fn probe(home: &Path) -> Option<Self> {
+        // macOS bundles keep the actual home in Contents/Home
+        let bundle_home = home.join("Contents").join("Home");
+        let home = if bundle_home.is_dir() {
+            bundle_home
+        } else {
+            home.to_path_buf()
+        };
+
+        if !LIBJVM_PATHS.iter().any(|path| home.join(path).is_file()) {
+            return None;
+        }
+        let version = read_release_version(&home).unwrap_or_default();
+        let major = major_version(&version).unwrap_or(0);
+        Some(Self {
+            home,
+            version,
+            major,
+        })
+    }
+}
+
+/// Finds the Java runtime j4rs will start and checks that it is recent enough.
+///
+/// j4rs loads `libjvm` from `JAVA_HOME`, or from the `java` on the `PATH` when it is unset,
+/// and cannot be pointed elsewhere. Changing `JAVA_HOME` from here would race other threads
+/// reading the environment, so this never selects a runtime: `jvm.java-home` in
+/// patchbukkit.yml only makes startup fail when j4rs would load another installation, and
+/// the Java installations in the usual install folders are only named in error messages.
+pub fn locate_java_runtime(config: &JvmConfig) -> Result<JavaRuntime> {
+    let source = if env::var_os("JAVA_HOME").is_some_and(|home| !home.is_empty()) {
+        "JAVA_HOME"
+    } else {
+        "the java on the PATH"
+    };
+    let home = java_locator::locate_java_home().map_err(|e| {
+        let hint = match &config.java_home {
+            Some(configured) => format!(
+                ". jvm.java-home in patchbukkit.yml is {}, start the server with JAVA_HOME={}",
+                configured.display(),
+                configured.display()
+            ),
+            None => installed_hint(),
+        };
+        anyhow!(
+            "No Java runtime found ({}). Install Java {} or newer, then start the server with \
+             JAVA_HOME set to its folder{}",
+            e,
+            MINIMUM_JAVA_VERSION,
+            hint
+        )
+    })?;
+    let home = PathBuf::from(home);
+
+    if let Some(configured) = &config.java_home
+        && !same_path(configured, &home)
+    {
+        return Err(anyhow!(
+            "jvm.java-home in patchbukkit.yml is {}, but the JVM is loaded from {} ({}). \
+             Start the server with JAVA_HOME={}",
+            configured.display(),
+            source,
+            home.display(),
+            configured.display()
+        ));
+    }
+
+    let runtime = JavaRuntime::probe(&home).ok_or_else(|| {
+        anyhow!(
+            "{} points to {}, which is not a Java installation (no libjvm found). \
+             Point it to a Java {} or newer installation{}",
+            source,
+            home.display(),
+            MINIMUM_JAVA_VERSION,
+            installed_hint()
+        )
+    })?;
+    check_version(&runtime, source)?;
+
+    log::info!(
+        "Using Java {} from {}",
+        runtime.version,
+        runtime.home.display()
+    );
+    Ok(runtime)
+}
+
+/// Names the newest suitable Java in the usual install folders, for error messages
+fn installed_hint() -> String {
+    let newest = INSTALL_ROOTS
+        .iter()
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flatten()
+        .filter_map(|entry| JavaRuntime::probe(&entry.ok()?.path()))
+        .filter(|runtime| runtime.major >= MINIMUM_JAVA_VERSION)
+        .max_by_key(|runtime| runtime.major);
+    match newest {
+        Some(runtime) => format!(
+            ". Java {} is installed at {}, start the server with JAVA_HOME={}",
+            runtime.version,
+            runtime.home.display(),
+            runtime.home.display()
+        ),
+        None => format!(" (searched {})", INSTALL_ROOTS.join(", ")),
+    }
+}
+
+/// Whether both paths name the same folder, following symlinks when they exist
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn check_version(runtime: &JavaRuntime, source: &str) -> Result<()> {
+    if runtime.major >= MINIMUM_JAVA_VERSION {
+        return Ok(());
+    }
+    let found = if runtime.version.is_empty() {
+        "an unknown version".to_string()
+    } else {
+        format!("Java {}", runtime.version)
+    };
+    Err(anyhow!(
+        "{} ({}) is {}, but PatchBukkit needs Java {} or newer. Install Java {} and start \
+         the server with JAVA_HOME pointing to it{}",
+        source,
+        runtime.home.display(),
+        found,
+        MINIMUM_JAVA_VERSION,
+        MINIMUM_JAVA_VERSION,
+        installed_hint()
+    ))
+}
+
+/// The `JAVA_VERSION` entry of the `release` file every JDK and JRE ships
+fn read_release_version(home: &Path) -> Option<String> {
+    let release = fs::read_to_string(home.join("release")).ok()?;
+    release.lines().find_map(|line| {
+        let value = line.strip_prefix("JAVA_VERSION=")?;
+        Some(value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// `25.0.1` is 25, the legacy `1.8.0_402` scheme is 8
+fn major_version(version: &str) -> Option<u32> {
+    let mut parts = version.split(['.', '_', '-', '+']);
+    let first: u32 = parts.next()?.parse().ok()?;
+    if first == 1 {
+        parts.next()?.parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn major_versions() {
+        assert_eq!(major_version("25"), Some(25));
+        assert_eq!(major_version("25.0.1"), Some(25));
+        assert_eq!(major_version("26-ea"), Some(26));
+        assert_eq!(major_version("21.0.5+11"), Some(21));
+        assert_eq!(major_version("1.8.0_402"), Some(8));
+        assert_eq!(major_version(""), None);
+        assert_eq!(major_version("latest"), None);
+    }
+
+    #[test]
+    fn release_version() {
+        let home = env::temp_dir().join(format!("patchbukkit-release-{}", std::process::id()));
+        fs::create_dir_all(&home).unwrap();
+        assert_eq!(read_release_version(&home), None);
+
+        fs::write(
+            home.join("release"),
+            "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"25.0.1\"\nOS_NAME=\"Linux\"\n",
+        )
+        .unwrap();
+        assert_eq!(read_release_version(&home).as_deref(), Some("25.0.1"));
+
+        fs::write(home.join("release"), "IMPLEMENTOR=\"Oracle\"\n").unwrap();
+        assert_eq!(read_release_version(&home), None);
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
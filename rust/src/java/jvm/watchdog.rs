@@ -0,0 +1,181 @@
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use j4rs::{InvocationArg, Jvm};
+
+use crate::config::patchbukkit::WatchdogConfig;
+
+const WATCHDOG_CLASS: &str = "org.patchbukkit.PatchBukkitWatchdog";
+
+/// How often the watchdog thread looks at the JVM worker
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+static STARTED: OnceLock<()> = OnceLock::new();
+
+/// The request the JVM worker is handling, `None` while it waits for the next one
+static BUSY: Mutex<Option<Busy>> = Mutex::new(None);
+
+/// Plugins the watchdog caught blocking the worker, disabled by the worker once it is free.
+///
+/// Events are not forwarded to these plugins in the meantime.
+static STUCK_PLUGINS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+struct Busy {
+    command: String,
+    plugin: Option<String>,
+    started: Instant,
+    /// How many times the watchdog reported this request
+    reports: u32,
+}
+
+/// Clears the worker's current request when dropped
+pub struct WatchGuard(());
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        *BUSY.lock().unwrap() = None;
+    }
+}
+
+/// Marks the JVM worker as busy with `command`, run on behalf of `plugin` if known
+pub fn watch(command: String, plugin: Option<String>) -> WatchGuard {
+    *BUSY.lock().unwrap() = Some(Busy {
+        command,
+        plugin,
+        started: Instant::now(),
+        reports: 0,
+    });
+    WatchGuard(())
+}
+
+/// Records the plugin a request covering several plugins, such as enabling all of them,
+/// is running right now
+pub fn running_plugin(name: &str) {
+    if let Some(busy) = BUSY.lock().unwrap().as_mut() {
+        busy.plugin = Some(name.to_string());
+    }
+}
+
+/// Whether the watchdog caught `plugin` blocking the worker and it was not disabled yet
+pub fn is_stuck(plugin: &str) -> bool {
+    STUCK_PLUGINS.lock().unwrap().contains(plugin)
+}
+
+/// Takes the plugins to disable because they blocked the worker
+pub fn take_stuck_plugins() -> Vec<String> {
+    STUCK_PLUGINS.lock().unwrap().drain().collect()
+}
+
+/// Starts the watchdog thread, called on the JVM worker thread once the JVM is running.
+///
+/// Only the first call has an effect.
+pub fn start(jvm: &Jvm, config: &WatchdogConfig) -> Result<()> {
+    if config.timeout_seconds == 0 || STARTED.set(()).is_err() {
+        return Ok(());
+    }
+
+    jvm.invoke_static(WATCHDOG_CLASS, "watchCurrentThread", InvocationArg::empty())?;
+
+    log::info!(
+        "Starting the watchdog, reporting requests running longer than {}s",
+        config.timeout_seconds
+    );
+    let config = config.clone();
+    thread::Builder::new()
+        .name("patchbukkit-watchdog".to_string())
+        .spawn(move || run(config))?;
+    Ok(())
+}
+
+/// Clears an interrupt the watchdog left on the worker thread that the plugin ignored
+pub fn clear_interrupt(jvm: &Jvm) -> Result<()> {
+    jvm.invoke_static("java.lang.Thread", "interrupted", InvocationArg::empty())?;
+    Ok(())
+}
+
+fn run(config: WatchdogConfig) {
+    let jvm = match Jvm::attach_thread() {
+        Ok(jvm) => jvm,
+        Err(e) => {
+            log::error!("Watchdog could not attach to the JVM: {}", e);
+            return;
+        }
+    };
+    let timeout = Duration::from_secs(config.timeout_seconds);
+
+    loop {
+        thread::sleep(CHECK_INTERVAL);
+
+        let stuck = match BUSY.lock().unwrap().as_mut() {
+            Some(busy) if busy.started.elapsed() >= timeout * (busy.reports + 1) => {
+                busy.reports += 1;
+                Some((
+                    busy.command.clone(),
+                    busy.plugin.clone(),
+                    busy.started.elapsed(),
+                    busy.reports,
+                ))
+            }
+            _ => None,
+        };
+        let Some((command, plugin, elapsed, reports)) = stuck else {
+            continue;
+        };
+        let plugin_name = plugin.as_deref().unwrap_or("an unknown plugin");
+
+        // Later reports only say the worker is still stuck, one thread dump is enough
+        if reports > 1 {
+            log::error!(
+                "The JVM worker is still running {} for {} after {}s",
+                command,
+                plugin_name,
+                elapsed.as_secs()
+            );
+            continue;
+        }
+
+        log::error!("------------------------------");
+        log::error!(
+            "The JVM worker has been running {} for {} for {}s, Bukkit plugins are frozen until it returns",
+            command,
+            plugin_name,
+            elapsed.as_secs()
+        );
+        match dump_threads(&jvm) {
+            Ok(dump) => {
+                log::error!("Java thread dump:");
+                for line in dump.lines() {
+                    log::error!("{}", line);
+                }
+            }
+            Err(e) => log::error!("Failed to capture a Java thread dump: {}", e),
+        }
+        log::error!("------------------------------");
+
+        if let Some(plugin) = plugin
+            && config.disable_stuck_plugins
+        {
+            STUCK_PLUGINS.lock().unwrap().insert(plugin.clone());
+            if let Err(e) =
+                jvm.invoke_static(WATCHDOG_CLASS, "interruptWorker", InvocationArg::empty())
+            {
+                log::error!("Failed to interrupt the JVM worker: {}", e);
+            }
+            log::warn!(
+                "Interrupted {}, it will be disabled once the JVM worker is free",
+                plugin
+            );
+        }
+    }
+}
+
+fn dump_threads(jvm: &Jvm) -> Result<String> {
+    let dump = jvm.invoke_static(WATCHDOG_CLASS, "dumpThreads", InvocationArg::empty())?;
+    Ok(jvm.to_rust(dump)?)
+}
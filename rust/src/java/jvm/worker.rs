@@ -1,15 +1,17 @@
 use std::{path::PathBuf, sync::Arc};
 
-use j4rs::{InvocationArg, Jvm, JvmBuilder};
+use j4rs::{InvocationArg, JavaOpt, Jvm, JvmBuilder};
 use pumpkin::plugin::Context;
 use tokio::sync::mpsc;
 
-use crate::java::{
-    jar::read_configs_from_jar,
-    jvm::commands::{JvmCommand, LoadPluginResult},
-    native_callbacks::{init_callback_context, initialize_callbacks},
-    plugin::{
-        command_manager::CommandManager, event_manager::EventManager, manager::PluginManager,
+use crate::{
+    config::patchbukkit::JvmConfig,
+    java::{
+        jvm::{commands::JvmCommand, runtime::locate_java_runtime, watchdog},
+        native_callbacks::{init_callback_context, initialize_callbacks},
+        plugin::{
+            command_manager::CommandManager, event_manager::EventManager, manager::PluginManager,
+        },
     },
 };
 
@@ -20,6 +22,8 @@ pub struct JvmWorker {
     pub command_manager: CommandManager,
     jvm: Option<j4rs::Jvm>,
     context: Option<Arc<Context>>,
+    /// Handed to plugins instantiated after startup, such as on reload
+    command_tx: Option<mpsc::Sender<JvmCommand>>,
 }
 
 impl JvmWorker {
@@ -31,6 +35,7 @@ impl JvmWorker {
             command_manager: CommandManager::new(),
             jvm: None,
             context: None,
+            command_tx: None,
         }
     }
 
@@ -38,13 +43,34 @@ impl JvmWorker {
         log::info!("JVM worker thread started");
 
         while let Some(command) = self.command_rx.recv().await {
+            self.disable_stuck_plugins().await;
+
+            if command.is_abandoned() {
+                log::debug!(
+                    "Skipping {}, its caller stopped waiting",
+                    command.describe()
+                );
+                continue;
+            }
+
+            let plugin = match &command {
+                JvmCommand::TriggerCommand { full_command, .. } => {
+                    self.plugin_manager.command_owner(full_command)
+                }
+                command => command.plugin().map(str::to_string),
+            };
+            let _watch = watchdog::watch(command.describe(), plugin);
+
             match command {
                 JvmCommand::Initialize {
                     j4rs_path,
                     respond_to,
                     context,
                     command_tx,
+                    config,
                 } => {
+                    self.command_manager
+                        .configure(&config.commands, command_tx.clone());
                     init_callback_context(
                         context.clone(),
                         tokio::runtime::Handle::current(),
@@ -52,46 +78,31 @@ impl JvmWorker {
                     )
                     .unwrap();
                     self.context = Some(context);
-                    let result = self.initialize_jvm(&j4rs_path);
+                    self.command_tx = Some(command_tx);
+                    let result = self.initialize_jvm(&j4rs_path, &config.jvm);
+                    if let (Ok(()), Some(jvm)) = (&result, &self.jvm)
+                        && let Err(e) = watchdog::start(jvm, &config.watchdog)
+                    {
+                        log::error!("Failed to start the watchdog: {}", e);
+                    }
                     let _ = respond_to.send(result);
                 }
+                JvmCommand::Configure { config } => {
+                    self.plugin_manager.configure(&config.plugins);
+                }
                 JvmCommand::LoadPlugin {
                     plugin_path,
                     respond_to,
                 } => {
-                    let _ = match read_configs_from_jar(&plugin_path) {
-                        Ok(configs) => match configs {
-                            (Some(paper_plugin_config), spigot @ _) => {
-                                match self.plugin_manager.load_paper_plugin(
-                                    &plugin_path,
-                                    &paper_plugin_config,
-                                    &spigot,
-                                ) {
-                                    Ok(_) => {
-                                        respond_to.send(LoadPluginResult::SuccessfullyLoadedPaper)
-                                    }
-                                    Err(err) => respond_to
-                                        .send(LoadPluginResult::FailedToLoadPaperPlugin(err)),
-                                }
-                            }
-                            (None, Some(spigot)) => {
-                                match self
-                                    .plugin_manager
-                                    .load_spigot_plugin(&plugin_path, &spigot)
-                                {
-                                    Ok(_) => {
-                                        respond_to.send(LoadPluginResult::SuccessfullyLoadedSpigot)
-                                    }
-                                    Err(err) => respond_to
-                                        .send(LoadPluginResult::FailedToLoadSpigotPlugin(err)),
-                                }
-                            }
-                            (None, None) => respond_to.send(LoadPluginResult::NoConfigurationFile),
-                        },
-                        Err(err) => {
-                            respond_to.send(LoadPluginResult::FailedToReadConfigurationFile(err))
-                        }
+                    let _ = respond_to.send(self.plugin_manager.load_plugin(&plugin_path));
+                }
+                JvmCommand::BootstrapAllPlugins { respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
                     };
+
+                    let _ = respond_to.send(self.plugin_manager.bootstrap_all_plugins(jvm));
                 }
                 JvmCommand::InstantiateAllPlugins {
                     respond_to,
@@ -114,13 +125,25 @@ impl JvmWorker {
                             .await,
                     );
                 }
-                JvmCommand::EnableAllPlugins { respond_to } => {
+                JvmCommand::GetDependencyReport { respond_to } => {
+                    let _ = respond_to.send(self.plugin_manager.dependency_report().clone());
+                }
+                JvmCommand::EnableAllPlugins { load, respond_to } => {
                     let jvm = match self.jvm {
                         Some(ref jvm) => jvm,
                         None => &Jvm::attach_thread().unwrap(),
                     };
 
-                    let _ = respond_to.send(self.plugin_manager.enable_all_plugins(jvm));
+                    let Some(context) = self.context.clone() else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let _ = respond_to.send(
+                        self.plugin_manager
+                            .enable_all_plugins(jvm, &context, load)
+                            .await,
+                    );
                 }
                 JvmCommand::DisableAllPlugins { respond_to } => {
                     let jvm = match self.jvm {
@@ -128,7 +151,108 @@ impl JvmWorker {
                         None => &Jvm::attach_thread().unwrap(),
                     };
 
-                    let _ = respond_to.send(self.plugin_manager.disable_all_plugins(jvm));
+                    let Some(context) = self.context.clone() else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let _ = respond_to
+                        .send(self.plugin_manager.disable_all_plugins(jvm, &context).await);
+                }
+                JvmCommand::ReloadPlugin { name, respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
+                    };
+                    let (Some(context), Some(command_tx)) =
+                        (self.context.clone(), self.command_tx.clone())
+                    else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let _ = respond_to.send(
+                        self.plugin_manager
+                            .reload_plugin(
+                                jvm,
+                                &context,
+                                command_tx,
+                                &mut self.command_manager,
+                                &name,
+                            )
+                            .await,
+                    );
+                }
+                JvmCommand::UnloadPlugin { name, respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
+                    };
+                    let Some(context) = self.context.clone() else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let result = self
+                        .plugin_manager
+                        .unload_plugin(jvm, &context, &name)
+                        .await
+                        .map(|_| ());
+                    let _ = respond_to.send(result);
+                }
+                JvmCommand::ListPlugins { respond_to } => {
+                    let _ = respond_to.send(self.plugin_manager.plugin_infos());
+                }
+                JvmCommand::GetPluginInfo { name, respond_to } => {
+                    let _ = respond_to.send(self.plugin_manager.plugin_info(&name));
+                }
+                JvmCommand::ListEventListeners { respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
+                    };
+
+                    let _ = respond_to.send(self.plugin_manager.event_listeners(jvm));
+                }
+                JvmCommand::EnablePlugin { name, respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
+                    };
+                    let (Some(context), Some(command_tx)) =
+                        (self.context.clone(), self.command_tx.clone())
+                    else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let _ = respond_to.send(
+                        self.plugin_manager
+                            .enable_plugin_by_name(
+                                jvm,
+                                &context,
+                                command_tx,
+                                &mut self.command_manager,
+                                &name,
+                            )
+                            .await,
+                    );
+                }
+                JvmCommand::DisablePlugin { name, respond_to } => {
+                    let jvm = match self.jvm {
+                        Some(ref jvm) => jvm,
+                        None => &Jvm::attach_thread().unwrap(),
+                    };
+                    let Some(context) = self.context.clone() else {
+                        let _ = respond_to.send(Err(anyhow::anyhow!("JVM was never initialized")));
+                        continue;
+                    };
+
+                    let _ = respond_to.send(
+                        self.plugin_manager
+                            .disable_plugin_by_name(jvm, &context, &name)
+                            .await,
+                    );
                 }
                 JvmCommand::Shutdown { respond_to } => {
                     let _ = respond_to.send(self.plugin_manager.unload_all_plugins());
@@ -179,7 +303,14 @@ impl JvmWorker {
                     full_command,
                     respond_to,
                     location,
+                    ticket,
                 } => {
+                    // The sender has typed further since, the client ignores this answer
+                    if ticket.is_stale() {
+                        let _ = respond_to.send(Ok(None));
+                        continue;
+                    }
+
                     let jvm = match self.jvm {
                         Some(ref jvm) => jvm,
                         None => &Jvm::attach_thread().unwrap(),
@@ -200,10 +331,56 @@ impl JvmWorker {
         log::info!("JVM worker thread exited");
     }
 
-    fn initialize_jvm(&mut self, j4rs_path: &PathBuf) -> anyhow::Result<()> {
+    /// Disables the plugins the watchdog caught blocking the worker
+    async fn disable_stuck_plugins(&mut self) {
+        let stuck = watchdog::take_stuck_plugins();
+        if stuck.is_empty() {
+            return;
+        }
+        let (Some(jvm), Some(context)) = (&self.jvm, self.context.clone()) else {
+            return;
+        };
+        if let Err(e) = watchdog::clear_interrupt(jvm) {
+            log::warn!("Failed to clear the JVM worker interrupt: {}", e);
+        }
+
+        for name in stuck {
+            match self
+                .plugin_manager
+                .disable_plugin_by_name(jvm, &context, &name)
+                .await
+            {
+                Ok(()) => log::warn!("Disabled {} because it blocked the JVM worker", name),
+                Err(e) => log::error!(
+                    "Failed to disable {} after it blocked the JVM worker: {}",
+                    name,
+                    e
+                ),
+            }
+        }
+    }
+
+    fn initialize_jvm(&mut self, j4rs_path: &PathBuf, config: &JvmConfig) -> anyhow::Result<()> {
         log::info!("Initializing JVM with path: {:?}", j4rs_path);
 
-        let jvm = JvmBuilder::new().with_base_path(j4rs_path).build()?;
+        locate_java_runtime(config)?;
+
+        let options = config.java_options();
+        if options.is_empty() {
+            log::info!("JVM options: none, using the JVM defaults");
+        } else {
+            log::info!("JVM options: {}", options.join(" "));
+        }
+
+        let jvm = JvmBuilder::new()
+            .with_base_path(j4rs_path)
+            .java_opts(
+                options
+                    .into_iter()
+                    .map(|option| JavaOpt::new(&option))
+                    .collect(),
+            )
+            .build()?;
 
         initialize_callbacks(&jvm)?;
 
@@ -3,7 +3,10 @@ use std::ffi::c_char;
 use pumpkin::entity::player::Abilities;
 use tokio::sync::MutexGuard;
 
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
+use crate::java::native_callbacks::{
+    status::{CallbackError, guard},
+    utils::{callback_context, get_mut, get_uuid},
+};
 
 #[repr(C)]
 pub struct AbilitiesFFI {
@@ -30,60 +33,52 @@ impl AbilitiesFFI {
     }
 }
 
-pub extern "C" fn rust_set_abilities(
-    uuid_ptr: *const c_char,
-    abilities: *mut AbilitiesFFI,
-) -> bool {
-    let uuid_str = get_string(uuid_ptr);
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let uuid = uuid::Uuid::parse_str(&uuid_str).unwrap();
-        let player = ctx.plugin_context.server.get_player_by_uuid(uuid);
-        if let Some(player) = player {
-            tokio::task::block_in_place(|| {
-                ctx.runtime.block_on(async {
-                    let mut server_abilities = player.abilities.lock().await;
-                    unsafe {
-                        server_abilities.allow_flying = (*abilities).allow_flying;
-                        server_abilities.allow_modify_world = (*abilities).allow_modify_world;
-                        server_abilities.creative = (*abilities).creative;
-                        server_abilities.fly_speed = (*abilities).fly_speed;
-                        server_abilities.flying = (*abilities).flying;
-                        server_abilities.invulnerable = (*abilities).invulnerable;
-                        server_abilities.walk_speed = (*abilities).walk_speed;
-                    }
-                })
-            });
+pub extern "C" fn rust_set_abilities(uuid_ptr: *const c_char, abilities: *mut AbilitiesFFI) -> i32 {
+    guard("rust_set_abilities", || {
+        let uuid = get_uuid(uuid_ptr, "uuid")?;
+        let abilities = get_mut(abilities, "abilities")?;
+        let ctx = callback_context()?;
+        let player = ctx
+            .plugin_context
+            .server
+            .get_player_by_uuid(uuid)
+            .ok_or_else(|| CallbackError::not_found(format!("No player {}", uuid)))?;
 
-            let player = player.clone();
-            ctx.runtime.spawn(async move {
-                player.send_abilities_update().await;
-            });
+        tokio::task::block_in_place(|| {
+            ctx.runtime.block_on(async {
+                let mut server_abilities = player.abilities.lock().await;
+                server_abilities.allow_flying = abilities.allow_flying;
+                server_abilities.allow_modify_world = abilities.allow_modify_world;
+                server_abilities.creative = abilities.creative;
+                server_abilities.fly_speed = abilities.fly_speed;
+                server_abilities.flying = abilities.flying;
+                server_abilities.invulnerable = abilities.invulnerable;
+                server_abilities.walk_speed = abilities.walk_speed;
+            })
+        });
 
-            return true;
-        }
-    }
-
-    false
+        ctx.runtime.spawn(async move {
+            player.send_abilities_update().await;
+        });
+        Ok(())
+    })
 }
 
-pub extern "C" fn rust_get_abilities(uuid_ptr: *const c_char, out: *mut AbilitiesFFI) -> bool {
-    let uuid_str = get_string(uuid_ptr);
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let uuid = uuid::Uuid::parse_str(&uuid_str).unwrap();
-        let player = ctx.plugin_context.server.get_player_by_uuid(uuid);
-        if let Some(player) = player {
-            let abilities = tokio::task::block_in_place(|| {
-                ctx.runtime
-                    .block_on(async { AbilitiesFFI::new(player.abilities.lock().await) })
-            });
-
-            unsafe {
-                *out = abilities;
-            }
-
-            return true;
-        }
-    }
+pub extern "C" fn rust_get_abilities(uuid_ptr: *const c_char, out: *mut AbilitiesFFI) -> i32 {
+    guard("rust_get_abilities", || {
+        let uuid = get_uuid(uuid_ptr, "uuid")?;
+        let out = get_mut(out, "out")?;
+        let ctx = callback_context()?;
+        let player = ctx
+            .plugin_context
+            .server
+            .get_player_by_uuid(uuid)
+            .ok_or_else(|| CallbackError::not_found(format!("No player {}", uuid)))?;
 
-    false
+        *out = tokio::task::block_in_place(|| {
+            ctx.runtime
+                .block_on(async { AbilitiesFFI::new(player.abilities.lock().await) })
+        });
+        Ok(())
+    })
 }
@@ -1,129 +1,196 @@
+use std::collections::HashMap;
 use std::ffi::c_char;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock, Mutex};
 
 use pumpkin::plugin::EventPriority;
 use pumpkin::plugin::player::player_join::PlayerJoinEvent;
 use pumpkin_util::text::TextComponent;
 
-use crate::events::handler::PatchBukkitEventHandler;
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
+use crate::events::handler::{ListenerHandle, PatchBukkitEventHandler};
+use crate::java::native_callbacks::{
+    status::{CallbackError, guard},
+    utils::{callback_context, get_string},
+};
+
+/// Listeners registered from Java that their plugin has not claimed yet.
+///
+/// Java registers listeners while the worker is busy enabling the plugin, so the
+/// handles are parked here until the `PluginManager` picks them up.
+static REGISTERED_LISTENERS: LazyLock<Mutex<HashMap<String, Vec<ListenerHandle>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Every handler registered with Pumpkin, by plugin, event class, priority and blocking.
+///
+/// Pumpkin keeps handlers forever, so the unregistered ones are reused when a plugin
+/// registers the same listener again.
+static PUMPKIN_HANDLERS: LazyLock<Mutex<HashMap<HandlerKey, Vec<ListenerHandle>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+type HandlerKey = (String, String, i32, bool);
+
+/// Parks the handle of a listener until its plugin claims it
+fn park_listener(plugin_name: &str, handle: ListenerHandle) {
+    REGISTERED_LISTENERS
+        .lock()
+        .unwrap()
+        .entry(plugin_name.to_string())
+        .or_default()
+        .push(handle);
+}
+
+/// Reactivates an unregistered handler Pumpkin already has for the same listener
+fn reuse_handler(key: &HandlerKey) -> Option<ListenerHandle> {
+    PUMPKIN_HANDLERS
+        .lock()
+        .unwrap()
+        .get(key)?
+        .iter()
+        .find(|handle| handle.reactivate())
+        .cloned()
+}
+
+/// Takes the handles of every listener registered for a plugin since the last call
+pub fn take_registered_listeners(plugin_name: &str) -> Vec<ListenerHandle> {
+    REGISTERED_LISTENERS
+        .lock()
+        .unwrap()
+        .remove(plugin_name)
+        .unwrap_or_default()
+}
 
 pub extern "C" fn rust_register_event(
     event_type_ptr: *const c_char,
     plugin_name_ptr: *const c_char,
     priority: i32,
     blocking: bool,
-) {
-    let event_type = get_string(event_type_ptr);
-    let plugin_name = get_string(plugin_name_ptr);
-
-    let Some(ctx) = CALLBACK_CONTEXT.get() else {
-        log::error!("CallbackContext not initialized when registering event");
-        return;
-    };
-
-    let pumpkin_priority = match priority {
-        0 => EventPriority::Lowest,
-        1 => EventPriority::Low,
-        2 => EventPriority::Normal,
-        3 => EventPriority::High,
-        _ => EventPriority::Highest,
-    };
-
-    log::info!(
-        "Plugin '{}' registering listener for '{}' (priority={:?}, blocking={})",
-        plugin_name,
-        event_type,
-        priority,
-        blocking
-    );
-
-    let command_tx = ctx.command_tx.clone();
-    let context = ctx.plugin_context.clone();
-    let event_type_owned = event_type.clone();
-
-    tokio::task::block_in_place(|| {
-        ctx.runtime.block_on(async {
-            match event_type_owned.as_str() {
-                "org.bukkit.event.player.PlayerJoinEvent" => {
-                    context
-                        .register_event::<
-                            pumpkin::plugin::player::player_join::PlayerJoinEvent,
-                            PatchBukkitEventHandler<pumpkin::plugin::player::player_join::PlayerJoinEvent>,
-                        >(
-                            Arc::new(PatchBukkitEventHandler::new(
-                                plugin_name.clone(),
-                                command_tx.clone(),
-                            )),
-                            pumpkin_priority,
-                            blocking,
-                        )
-                        .await;
-                }
-                _ => {
-                    log::warn!(
-                        "Unsupported Bukkit event type '{}' from plugin '{}'",
-                        event_type_owned, plugin_name
-                    );
+) -> i32 {
+    guard("rust_register_event", || {
+        let event_type = get_string(event_type_ptr, "event_type")?;
+        let plugin_name = get_string(plugin_name_ptr, "plugin_name")?;
+        let ctx = callback_context()?;
+
+        let pumpkin_priority = match priority {
+            0 => EventPriority::Lowest,
+            1 => EventPriority::Low,
+            2 => EventPriority::Normal,
+            3 => EventPriority::High,
+            _ => EventPriority::Highest,
+        };
+
+        log::info!(
+            "Plugin '{}' registering listener for '{}' (priority={:?}, blocking={})",
+            plugin_name,
+            event_type,
+            priority,
+            blocking
+        );
+
+        let key = (plugin_name.clone(), event_type.clone(), priority, blocking);
+        if let Some(handle) = reuse_handler(&key) {
+            log::debug!("Reusing the Pumpkin handler of a previous registration");
+            park_listener(&plugin_name, handle);
+            return Ok(());
+        }
+
+        let command_tx = ctx.command_tx.clone();
+        let context = ctx.plugin_context.clone();
+        let event_type_owned = event_type.clone();
+
+        tokio::task::block_in_place(|| {
+            ctx.runtime.block_on(async {
+                match event_type_owned.as_str() {
+                    "org.bukkit.event.player.PlayerJoinEvent" => {
+                        let handler = PatchBukkitEventHandler::new(
+                            plugin_name.clone(),
+                            command_tx.clone(),
+                        );
+                        let handle = handler.handle(&event_type_owned);
+                        PUMPKIN_HANDLERS
+                            .lock()
+                            .unwrap()
+                            .entry(key)
+                            .or_default()
+                            .push(handle.clone());
+                        park_listener(&plugin_name, handle);
+                        context
+                            .register_event::<
+                                pumpkin::plugin::player::player_join::PlayerJoinEvent,
+                                PatchBukkitEventHandler<pumpkin::plugin::player::player_join::PlayerJoinEvent>,
+                            >(
+                                Arc::new(handler),
+                                pumpkin_priority,
+                                blocking,
+                            )
+                            .await;
+                    }
+                    _ => {
+                        log::warn!(
+                            "Unsupported Bukkit event type '{}' from plugin '{}'",
+                            event_type_owned, plugin_name
+                        );
+                    }
                 }
-            }
+            });
         });
-    });
+        Ok(())
+    })
 }
 
 pub extern "C" fn rust_call_event(
     event_type_ptr: *const c_char,
     event_data_ptr: *const c_char,
-) -> bool {
-    let event_type = get_string(event_type_ptr);
-    let event_data_json = get_string(event_data_ptr);
-
-    let Some(ctx) = CALLBACK_CONTEXT.get() else {
-        log::error!("CallbackContext not initialized when calling event");
-        return false;
-    };
-
-    log::debug!(
-        "Java calling event '{}' with data: {}",
-        event_type,
-        event_data_json
-    );
-
-    let event_data: serde_json::Value = match serde_json::from_str(&event_data_json) {
-        Ok(v) => v,
-        Err(e) => {
-            log::error!("Failed to parse event data JSON: {}", e);
-            return false;
-        }
-    };
-
-    let context = ctx.plugin_context.clone();
-
-    let handled = tokio::task::block_in_place(|| {
-        ctx.runtime.block_on(async {
-            match event_type.as_str() {
-                "org.bukkit.event.player.PlayerJoinEvent" => {
-                    let player_uuid_str = event_data["playerUuid"].as_str().unwrap_or("");
-                    let join_message_str = event_data["joinMessage"].as_str().unwrap_or("");
-                    if let Ok(uuid) = uuid::Uuid::parse_str(player_uuid_str) {
-                        if let Some(player) = context.server.get_player_by_uuid(uuid) {
-                            let pumpkin_event = PlayerJoinEvent::new(
-                                player,
-                                TextComponent::from_legacy_string(join_message_str),
-                            );
-                            context.server.plugin_manager.fire(pumpkin_event).await;
-                            return true;
+) -> i32 {
+    guard("rust_call_event", || {
+        let event_type = get_string(event_type_ptr, "event_type")?;
+        let event_data_json = get_string(event_data_ptr, "event_data")?;
+        let ctx = callback_context()?;
+
+        log::debug!(
+            "Java calling event '{}' with data: {}",
+            event_type,
+            event_data_json
+        );
+
+        let event_data: serde_json::Value =
+            serde_json::from_str(&event_data_json).map_err(|e| {
+                CallbackError::invalid_argument(format!("Failed to parse event data JSON: {}", e))
+            })?;
+
+        let context = ctx.plugin_context.clone();
+
+        let handled = tokio::task::block_in_place(|| {
+            ctx.runtime.block_on(async {
+                match event_type.as_str() {
+                    "org.bukkit.event.player.PlayerJoinEvent" => {
+                        let player_uuid_str = event_data["playerUuid"].as_str().unwrap_or("");
+                        let join_message_str = event_data["joinMessage"].as_str().unwrap_or("");
+                        if let Ok(uuid) = uuid::Uuid::parse_str(player_uuid_str) {
+                            if let Some(player) = context.server.get_player_by_uuid(uuid) {
+                                let pumpkin_event = PlayerJoinEvent::new(
+                                    player,
+                                    TextComponent::from_legacy_string(join_message_str),
+                                );
+                                context.server.plugin_manager.fire(pumpkin_event).await;
+                                return true;
+                            }
                         }
+                        false
+                    }
+                    _ => {
+                        log::warn!("Unknown event type for Pumpkin: {}", event_type);
+                        false
                     }
-                    false
-                }
-                _ => {
-                    log::warn!("Unknown event type for Pumpkin: {}", event_type);
-                    false
                 }
-            }
-        })
-    });
+            })
+        });
 
-    handled
+        if handled {
+            Ok(())
+        } else {
+            Err(CallbackError::not_found(format!(
+                "Pumpkin did not handle {}",
+                event_type
+            )))
+        }
+    })
 }
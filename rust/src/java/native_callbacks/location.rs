@@ -2,7 +2,10 @@ use std::ffi::c_char;
 
 use pumpkin::command::args::entities::{EntitySelectorType, TargetSelector};
 
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
+use crate::java::native_callbacks::{
+    status::{CallbackError, guard},
+    utils::{callback_context, get_mut, get_uuid},
+};
 
 #[repr(C)]
 pub struct Vec3FFI {
@@ -11,28 +14,26 @@ pub struct Vec3FFI {
     pub z: f64,
 }
 
-pub extern "C" fn rust_get_location(uuid_ptr: *const c_char, out: *mut Vec3FFI) -> bool {
-    let uuid_str = get_string(uuid_ptr);
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let uuid = uuid::Uuid::parse_str(&uuid_str).unwrap();
+pub extern "C" fn rust_get_location(uuid_ptr: *const c_char, out: *mut Vec3FFI) -> i32 {
+    guard("rust_get_location", || {
+        let uuid = get_uuid(uuid_ptr, "uuid")?;
+        let out = get_mut(out, "out")?;
+        let ctx = callback_context()?;
+
         let entity = ctx
             .plugin_context
             .server
             .select_entities(&TargetSelector::new(EntitySelectorType::Uuid(uuid)), None);
-        if entity.len() == 1 {
-            let entity = entity.first().unwrap().get_entity();
-            let position =
-                tokio::task::block_in_place(|| ctx.runtime.block_on(async { entity.pos.load() }));
-
-            unsafe {
-                (*out).x = position.x;
-                (*out).y = position.y;
-                (*out).z = position.z;
-            }
-
-            return true;
-        }
-    }
+        let [entity] = entity.as_slice() else {
+            return Err(CallbackError::not_found(format!("No entity {}", uuid)));
+        };
+        let entity = entity.get_entity();
+        let position =
+            tokio::task::block_in_place(|| ctx.runtime.block_on(async { entity.pos.load() }));
 
-    false
+        out.x = position.x;
+        out.y = position.y;
+        out.z = position.z;
+        Ok(())
+    })
 }
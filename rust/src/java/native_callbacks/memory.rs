@@ -1,9 +1,15 @@
 use std::ffi::{CString, c_char};
 
-pub extern "C" fn rust_free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        unsafe {
-            drop(CString::from_raw(ptr));
+use crate::java::native_callbacks::status::guard;
+
+pub extern "C" fn rust_free_string(ptr: *mut c_char) -> i32 {
+    guard("rust_free_string", || {
+        if !ptr.is_null() {
+            // SAFETY: Java only passes strings it received from Rust through `into_raw`
+            unsafe {
+                drop(CString::from_raw(ptr));
+            }
         }
-    }
+        Ok(())
+    })
 }
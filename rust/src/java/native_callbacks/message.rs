@@ -2,14 +2,16 @@ use std::ffi::c_char;
 
 use pumpkin_util::text::TextComponent;
 
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
+use crate::java::native_callbacks::{
+    status::guard,
+    utils::{callback_context, get_string, get_uuid},
+};
 
-pub extern "C" fn rust_send_message(uuid_ptr: *const c_char, message_ptr: *const c_char) {
-    let uuid_str = get_string(uuid_ptr);
-    let message = get_string(message_ptr);
-
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let uuid = uuid::Uuid::parse_str(&uuid_str).unwrap();
+pub extern "C" fn rust_send_message(uuid_ptr: *const c_char, message_ptr: *const c_char) -> i32 {
+    guard("rust_send_message", || {
+        let uuid = get_uuid(uuid_ptr, "uuid")?;
+        let message = get_string(message_ptr, "message")?;
+        let ctx = callback_context()?;
 
         ctx.runtime.spawn(async move {
             let player = ctx.plugin_context.server.get_player_by_uuid(uuid);
@@ -19,5 +21,6 @@ pub extern "C" fn rust_send_message(uuid_ptr: *const c_char, message_ptr: *const
                     .await;
             }
         });
-    }
+        Ok(())
+    })
 }
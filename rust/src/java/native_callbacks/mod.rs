@@ -14,6 +14,7 @@ pub mod memory;
 pub mod message;
 pub mod registry;
 pub mod sound;
+pub mod status;
 pub mod utils;
 pub mod world;
 
@@ -55,6 +56,7 @@ pub fn initialize_callbacks(jvm: &Jvm) -> Result<()> {
     let rust_player_entity_play_sound_addr =
         sound::rust_player_entity_play_sound as *const () as i64;
     let rust_player_play_sound_addr = sound::rust_player_play_sound as *const () as i64;
+    let last_error_addr = status::rust_last_error as *const () as i64;
 
     jvm.invoke_static(
         "org.patchbukkit.bridge.NativePatchBukkit",
@@ -71,6 +73,7 @@ pub fn initialize_callbacks(jvm: &Jvm) -> Result<()> {
             InvocationArg::try_from(rust_get_registry_data_addr)?.into_primitive()?,
             InvocationArg::try_from(rust_player_entity_play_sound_addr)?.into_primitive()?,
             InvocationArg::try_from(rust_player_play_sound_addr)?.into_primitive()?,
+            InvocationArg::try_from(last_error_addr)?.into_primitive()?,
         ],
     )?;
 
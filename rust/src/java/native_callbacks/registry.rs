@@ -1,9 +1,12 @@
-use std::{collections::HashMap, ffi::CString, os::raw::c_char};
+use std::{collections::HashMap, os::raw::c_char};
 
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::java::native_callbacks::utils::get_string;
+use crate::java::native_callbacks::{
+    status::{CallbackError, guard},
+    utils::{get_string, put_string},
+};
 
 #[derive(Serialize, Debug, Clone)]
 struct Registry {
@@ -17,43 +20,38 @@ impl Registry {
     }
 }
 
-pub extern "C" fn rust_get_registry_data(registry_name: *const c_char) -> *const c_char {
-    let name = get_string(registry_name);
-
-    let registry: Option<Registry> = match name.as_ref() {
-        "sound_event" => {
-            let entries: Vec<serde_json::Value> = pumpkin_data::sound::Sound::slice()
-                .iter()
-                .map(|s| {
-                    serde_json::json!({
-                        "name": s.to_name(),
-                        "id": *s as u16
+/// Writes the entries and tags of a registry to `out` as JSON
+pub extern "C" fn rust_get_registry_data(
+    registry_name: *const c_char,
+    out: *mut *mut c_char,
+) -> i32 {
+    guard("rust_get_registry_data", || {
+        let name = get_string(registry_name, "registry_name")?;
+
+        let registry = match name.as_ref() {
+            "sound_event" => {
+                let entries: Vec<serde_json::Value> = pumpkin_data::sound::Sound::slice()
+                    .iter()
+                    .map(|s| {
+                        serde_json::json!({
+                            "name": s.to_name(),
+                            "id": *s as u16
+                        })
                     })
-                })
-                .collect();
-            Some(Registry::new(entries, HashMap::new()))
-        }
-        _ => None,
-    };
-
-    let registry = match registry {
-        Some(registry) => registry,
-        None => return std::ptr::null(),
-    };
-
-    let json_str = match serde_json::to_string(&registry) {
-        Ok(json_str) => json_str,
-        Err(err) => {
-            log::error!("Failed to serialize registry: {}", err);
-            return std::ptr::null();
-        }
-    };
-
-    match CString::new(json_str) {
-        Ok(cstring) => cstring.into_raw(),
-        Err(err) => {
-            log::error!("Failed to convert registry string to CString: {}", err);
-            return std::ptr::null_mut();
-        }
-    }
+                    .collect();
+                Registry::new(entries, HashMap::new())
+            }
+            _ => {
+                return Err(CallbackError::not_found(format!(
+                    "Unknown registry {}",
+                    name
+                )));
+            }
+        };
+
+        let json_str = serde_json::to_string(&registry).map_err(|err| {
+            CallbackError::invalid_argument(format!("Failed to serialize registry: {}", err))
+        })?;
+        put_string(out, json_str)
+    })
 }
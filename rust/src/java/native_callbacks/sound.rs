@@ -6,7 +6,10 @@ use pumpkin_protocol::{IdOr, java::client::play::CEntitySoundEffect};
 use pumpkin_util::math::vector3::Vector3;
 use rand::{RngExt, rng};
 
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
+use crate::java::native_callbacks::{
+    status::guard,
+    utils::{callback_context, get_string, get_uuid},
+};
 
 pub extern "C" fn rust_player_entity_play_sound(
     player_uuid_ptr: *const c_char,
@@ -15,15 +18,13 @@ pub extern "C" fn rust_player_entity_play_sound(
     entity_uuid_ptr: *const c_char,
     volume: c_float,
     pitch: c_float,
-) {
-    let player_uuid_str = get_string(player_uuid_ptr);
-    let sound_name = get_string(sound_name_ptr);
-    let sound_category = get_string(sound_category_ptr);
-    let entity_uuid_str = get_string(entity_uuid_ptr);
-
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let player_uuid = uuid::Uuid::parse_str(&player_uuid_str).unwrap();
-        let entity_uuid = uuid::Uuid::parse_str(&entity_uuid_str).unwrap();
+) -> i32 {
+    guard("rust_player_entity_play_sound", || {
+        let player_uuid = get_uuid(player_uuid_ptr, "player_uuid")?;
+        let sound_name = get_string(sound_name_ptr, "sound_name")?;
+        let sound_category = get_string(sound_category_ptr, "sound_category")?;
+        let entity_uuid = get_uuid(entity_uuid_ptr, "entity_uuid")?;
+        let ctx = callback_context()?;
 
         ctx.runtime.spawn(async move {
             let player = ctx.plugin_context.server.get_player_by_uuid(player_uuid);
@@ -64,7 +65,8 @@ pub extern "C" fn rust_player_entity_play_sound(
                     .await;
             }
         });
-    }
+        Ok(())
+    })
 }
 
 pub extern "C" fn rust_player_play_sound(
@@ -76,13 +78,12 @@ pub extern "C" fn rust_player_play_sound(
     z: c_double,
     volume: c_float,
     pitch: c_float,
-) {
-    let player_uuid_str = get_string(player_uuid_ptr);
-    let sound_name = get_string(sound_name_ptr);
-    let sound_category = get_string(sound_category_ptr);
-
-    if let Some(ctx) = CALLBACK_CONTEXT.get() {
-        let player_uuid = uuid::Uuid::parse_str(&player_uuid_str).unwrap();
+) -> i32 {
+    guard("rust_player_play_sound", || {
+        let player_uuid = get_uuid(player_uuid_ptr, "player_uuid")?;
+        let sound_name = get_string(sound_name_ptr, "sound_name")?;
+        let sound_category = get_string(sound_category_ptr, "sound_category")?;
+        let ctx = callback_context()?;
 
         ctx.runtime.spawn(async move {
             let player = ctx.plugin_context.server.get_player_by_uuid(player_uuid);
@@ -107,5 +108,6 @@ pub extern "C" fn rust_player_play_sound(
                     .await;
             }
         });
-    }
+        Ok(())
+    })
 }
@@ -0,0 +1,106 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{CString, c_char},
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// The callback did what was asked
+pub const STATUS_OK: i32 = 0;
+/// The player, entity or registry asked for does not exist, Java answers null or false
+pub const STATUS_NOT_FOUND: i32 = 1;
+/// A pointer was null or misaligned, or a string was not a valid UUID
+pub const STATUS_INVALID_ARGUMENT: i32 = 2;
+/// Java called back before `init_callback_context` ran
+pub const STATUS_NOT_INITIALIZED: i32 = 3;
+/// The callback panicked, the panic was stopped at the FFI boundary
+pub const STATUS_PANIC: i32 = 4;
+
+thread_local! {
+    /// Message of the last failed callback on this thread, read by Java through
+    /// `rust_last_error`
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Why a callback could not complete, reported to Java as a status code
+#[derive(Debug)]
+pub struct CallbackError {
+    pub status: i32,
+    pub message: String,
+}
+
+impl CallbackError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: STATUS_NOT_FOUND,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self {
+            status: STATUS_INVALID_ARGUMENT,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_initialized() -> Self {
+        Self {
+            status: STATUS_NOT_INITIALIZED,
+            message: "The PatchBukkit callback context is not initialized".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CallbackError {}
+
+pub type CallbackResult<T = ()> = Result<T, CallbackError>;
+
+/// Runs the body of an `extern "C"` callback and turns its outcome into a status code.
+///
+/// A panic would unwind into the JVM, which is undefined behaviour, so it is caught here
+/// and reported as `STATUS_PANIC`. The message of any failure is kept for `rust_last_error`.
+pub fn guard(callback: &str, body: impl FnOnce() -> CallbackResult) -> i32 {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => (STATUS_OK, None),
+        Ok(Err(error)) => {
+            if error.status != STATUS_NOT_FOUND {
+                log::warn!("Native callback {} failed: {}", callback, error.message);
+            }
+            (error.status, Some(error.message))
+        }
+        Err(payload) => {
+            let message = format!("{} panicked: {}", callback, panic_message(&*payload));
+            log::error!("Native callback {}", message);
+            (STATUS_PANIC, Some(message))
+        }
+    };
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+    status
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic payload"
+    }
+}
+
+/// The message of the last failed callback on the calling thread, or null when the last
+/// callback succeeded. Java frees it with `rust_free_string`.
+pub extern "C" fn rust_last_error() -> *mut c_char {
+    let message = LAST_ERROR.with(|last_error| last_error.borrow_mut().take());
+    message
+        .and_then(|message| CString::new(message.replace('\0', " ")).ok())
+        .map_or(std::ptr::null_mut(), CString::into_raw)
+}
@@ -1,5 +1,60 @@
-use std::ffi::{CStr, c_char};
+use std::ffi::{CStr, CString, c_char};
 
-pub fn get_string(str_ptr: *const c_char) -> String {
-    unsafe { CStr::from_ptr(str_ptr).to_string_lossy().into_owned() }
+use uuid::Uuid;
+
+use crate::java::native_callbacks::{
+    CALLBACK_CONTEXT, CallbackContext,
+    status::{CallbackError, CallbackResult},
+};
+
+/// Reads a NUL terminated string passed from Java, refusing null pointers
+pub fn get_string(str_ptr: *const c_char, name: &str) -> CallbackResult<String> {
+    if str_ptr.is_null() {
+        return Err(CallbackError::invalid_argument(format!("{} is null", name)));
+    }
+    // SAFETY: the pointer is not null, and Java passes strings it allocated with
+    // `Arena::allocateFrom`, which are NUL terminated and live for the whole call
+    Ok(unsafe { CStr::from_ptr(str_ptr) }
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// Reads a UUID passed from Java as a string
+pub fn get_uuid(uuid_ptr: *const c_char, name: &str) -> CallbackResult<Uuid> {
+    let uuid = get_string(uuid_ptr, name)?;
+    Uuid::parse_str(&uuid).map_err(|e| {
+        CallbackError::invalid_argument(format!("{} `{}` is not a UUID: {}", name, uuid, e))
+    })
+}
+
+/// Borrows a struct Java allocated for the call, refusing null and misaligned pointers
+pub fn get_mut<'a, T>(ptr: *mut T, name: &str) -> CallbackResult<&'a mut T> {
+    if ptr.is_null() {
+        return Err(CallbackError::invalid_argument(format!("{} is null", name)));
+    }
+    if !ptr.is_aligned() {
+        return Err(CallbackError::invalid_argument(format!(
+            "{} is not aligned",
+            name
+        )));
+    }
+    // SAFETY: the pointer is not null and aligned, and Java allocates it with the layout
+    // of `T` for the duration of the call
+    Ok(unsafe { &mut *ptr })
+}
+
+/// Hands a string to Java through `out`, Java frees it with `rust_free_string`
+pub fn put_string(out: *mut *mut c_char, value: String) -> CallbackResult {
+    let out = get_mut(out, "out")?;
+    let value = CString::new(value).map_err(|e| {
+        CallbackError::invalid_argument(format!("String contains a NUL byte: {}", e))
+    })?;
+    *out = value.into_raw();
+    Ok(())
+}
+
+pub(super) fn callback_context() -> CallbackResult<&'static CallbackContext> {
+    CALLBACK_CONTEXT
+        .get()
+        .ok_or_else(CallbackError::not_initialized)
 }
@@ -1,39 +1,28 @@
-use std::ffi::{CString, c_char};
+use std::ffi::c_char;
 
 use pumpkin::command::args::entities::{EntitySelectorType, TargetSelector};
 
-use crate::java::native_callbacks::{CALLBACK_CONTEXT, utils::get_string};
-
-pub extern "C" fn rust_get_world(entity_uuid_ptr: *const c_char) -> *mut c_char {
-    if entity_uuid_ptr.is_null() {
-        return std::ptr::null_mut();
-    }
-
-    let entity_uuid = get_string(entity_uuid_ptr);
-
-    let Some(ctx) = CALLBACK_CONTEXT.get() else {
-        return std::ptr::null_mut();
-    };
-
-    let Ok(uuid) = uuid::Uuid::parse_str(&entity_uuid) else {
-        return std::ptr::null_mut();
-    };
-
-    let entities = ctx
-        .plugin_context
-        .server
-        .select_entities(&TargetSelector::new(EntitySelectorType::Uuid(uuid)), None);
-
-    if entities.len() != 1 {
-        return std::ptr::null_mut();
-    }
-
-    let entity = entities.first().unwrap().get_entity();
-    let world = entity.world.load();
-    let world_uuid = world.uuid.to_string();
-
-    match CString::new(world_uuid) {
-        Ok(cstring) => cstring.into_raw(), // Transfers ownership to caller
-        Err(_) => std::ptr::null_mut(),
-    }
+use crate::java::native_callbacks::{
+    status::{CallbackError, guard},
+    utils::{callback_context, get_uuid, put_string},
+};
+
+/// Writes the UUID of the world the entity is in to `out`
+pub extern "C" fn rust_get_world(entity_uuid_ptr: *const c_char, out: *mut *mut c_char) -> i32 {
+    guard("rust_get_world", || {
+        let uuid = get_uuid(entity_uuid_ptr, "entity_uuid")?;
+        let ctx = callback_context()?;
+
+        let entities = ctx
+            .plugin_context
+            .server
+            .select_entities(&TargetSelector::new(EntitySelectorType::Uuid(uuid)), None);
+        let [entity] = entities.as_slice() else {
+            return Err(CallbackError::not_found(format!("No entity {}", uuid)));
+        };
+
+        let world = entity.get_entity().world.load();
+        // Transfers ownership to the caller
+        put_string(out, world.uuid.to_string())
+    })
 }
@@ -1,28 +1,53 @@
 use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use j4rs::{Instance, InvocationArg, Jvm};
 use pumpkin::{command::dispatcher::CommandError, plugin::Context};
 use pumpkin_protocol::java::client::play::CommandSuggestion;
-use pumpkin_util::permission::{Permission, PermissionDefault};
+use pumpkin_util::{
+    permission::{Permission, PermissionDefault},
+    text::TextComponent,
+};
+use serde::Deserialize;
 use tokio::sync::mpsc;
 
 use crate::{
-    commands::{SimpleCommandSender, init_java_command},
-    config,
+    commands::{
+        CommandPermission, SimpleCommandSender, init_java_command, tab_complete::TabCompleter,
+    },
+    config::{self, patchbukkit::CommandsConfig},
     java::{
-        jvm::commands::{JvmCommand, Location},
-        plugin::manager::Plugin,
+        jvm::commands::{CommandOutcome, JvmCommand, Location},
+        plugin::{
+            manager::Plugin,
+            permission_manager::{bukkit_permission_node, register_permission},
+        },
     },
 };
 
+/// A completion as produced by `PatchBukkitCommandMap.tabCompleteWithTooltips`
+#[derive(Deserialize)]
+struct TabCompletion {
+    text: String,
+    #[serde(default)]
+    tooltip: Option<String>,
+}
+
 pub struct CommandManager {
     command_map: Option<Instance>,
+    tab_completer: Option<Arc<TabCompleter>>,
 }
 
 impl CommandManager {
     pub fn new() -> Self {
-        Self { command_map: None }
+        Self {
+            command_map: None,
+            tab_completer: None,
+        }
+    }
+
+    pub fn configure(&mut self, config: &CommandsConfig, command_tx: mpsc::Sender<JvmCommand>) {
+        self.tab_completer = Some(Arc::new(TabCompleter::new(command_tx, config)));
     }
 
     pub fn init(&mut self, jvm: &Jvm) -> Result<()> {
@@ -105,34 +130,46 @@ impl CommandManager {
 
             jvm.invoke(
                 command_map,
-                "tabComplete",
+                "tabCompleteWithTooltips",
                 &[
                     InvocationArg::try_from(sender)?,
-                    InvocationArg::try_from(full_command)?,
+                    InvocationArg::try_from(full_command.clone())?,
                     InvocationArg::try_from(location)?,
                 ],
             )?
         } else {
             jvm.invoke(
                 command_map,
-                "tabComplete",
+                "tabCompleteWithTooltips",
                 &[
                     InvocationArg::try_from(sender)?,
-                    InvocationArg::try_from(full_command)?,
+                    InvocationArg::try_from(full_command.clone())?,
                 ],
             )?
         };
 
-        let completions: Vec<String> = jvm.to_rust(completions)?;
+        let completions: String = jvm.to_rust(completions)?;
+        let completions: Vec<TabCompletion> = serde_json::from_str(&completions)?;
 
         Ok(Some(
             completions
                 .into_iter()
-                .map(|completion| CommandSuggestion::new(completion, None))
+                .map(|completion| {
+                    CommandSuggestion::new(
+                        suggestion_text(&full_command, &completion.text),
+                        completion
+                            .tooltip
+                            .map(|tooltip| TextComponent::from_legacy_string(&tooltip)),
+                    )
+                })
                 .collect(),
         ))
     }
 
+    /// Registers a `plugin.yml` command with the command map and Pumpkin.
+    ///
+    /// Returns the labels registered with Pumpkin, its name and aliases, and the permission
+    /// nodes registered for it.
     pub async fn register_command(
         &mut self,
         jvm: &Jvm,
@@ -141,7 +178,11 @@ impl CommandManager {
         cmd_name: String,
         cmd_data: &config::spigot::Command,
         command_tx: mpsc::Sender<JvmCommand>,
-    ) -> Result<()> {
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let tab_completer = self
+            .tab_completer
+            .clone()
+            .ok_or_else(|| anyhow!("Command manager has not been configured"))?;
         let command_map = match self.command_map {
             Some(ref command_map) => command_map,
             None => match self.init(jvm) {
@@ -161,9 +202,27 @@ impl CommandManager {
             "create",
             &[InvocationArg::try_from(&cmd_name)?, j_plugin_arg],
         )?));
-        log::info!("Registering Bukkit command: {}", &cmd_name);
+        let aliases = command_aliases(&cmd_name, cmd_data);
+        if aliases.is_empty() {
+            log::info!("Registering Bukkit command: {}", &cmd_name);
+        } else {
+            log::info!(
+                "Registering Bukkit command: {} (aliases: {})",
+                &cmd_name,
+                aliases.join(", ")
+            );
+        }
         {
             let cmd_lock = j_plugin_cmd.lock().unwrap();
+            if let Some(usage) = &cmd_data.usage {
+                // Sent by `PluginCommand.execute` when `onCommand` returns false
+                jvm.invoke(&*cmd_lock, "setUsage", &[InvocationArg::try_from(usage)?])?;
+            }
+            if !aliases.is_empty() {
+                // The command map registers every alias of the command it is given
+                let j_aliases = jvm.java_list("java.lang.String", aliases.clone())?;
+                jvm.invoke(&*cmd_lock, "setAliases", &[InvocationArg::from(j_aliases)])?;
+            }
             let j_plugin_cmd_owned = jvm.clone_instance(&*cmd_lock)?;
             jvm.invoke(
                 command_map,
@@ -176,33 +235,83 @@ impl CommandManager {
             )?;
         }
 
-        let node = init_java_command(
-            cmd_name.clone(),
-            command_tx.clone(),
-            [&cmd_name],
-            cmd_data.description.clone().unwrap_or_default(),
-        );
-
-        // TODO
-        // let permission = if let Some(perm) = cmd_data.permission.clone() {
-        //     perm
-        // } else {
-        //     format!("patchbukkit:{}", cmd_name) // TODO
-        // };
-        let permission = format!("patchbukkit:{}", cmd_name);
-
-        context
-            .register_permission(Permission::new(
-                &permission,
-                &permission,
-                PermissionDefault::Allow,
-            ))
+        let declared_permission = cmd_data
+            .permission
+            .as_deref()
+            .map(str::trim)
+            .filter(|permission| !permission.is_empty());
+
+        // Commands without a declared permission are open to everyone, as on Bukkit. So are
+        // commands with a `permission-message`, whose executor checks the permission instead
+        // so it can send the message.
+        let open_permission = format!("patchbukkit:{}", cmd_name);
+        let mut registered_permissions = Vec::new();
+        if (declared_permission.is_none() || cmd_data.permission_message.is_some())
+            && register_permission(
+                context,
+                Permission::new(&open_permission, &open_permission, PermissionDefault::Allow),
+            )
             .await
-            .unwrap();
+        {
+            registered_permissions.push(open_permission.clone());
+        }
 
-        context.register_command(node, permission).await;
+        let (permission, executor_permission) = match declared_permission {
+            Some(declared) => {
+                let node = bukkit_permission_node(declared);
+                let declaration = plugin
+                    .spigot_config()
+                    .and_then(|config| config.get_permission(declared));
+                let default = declaration
+                    .and_then(|permission| permission.default.clone())
+                    .or_else(|| {
+                        plugin
+                            .spigot_config()
+                            .and_then(|config| config.default_permission.clone())
+                    })
+                    .unwrap_or_default();
+                default.warn_unsupported(&plugin.name, declared);
+                let default =
+                    default.to_permission_default(context.server.basic_config.op_permission_level);
+                let description = declaration
+                    .and_then(|permission| permission.description.clone())
+                    .unwrap_or_else(|| format!("Allows using /{}", cmd_name));
+
+                if register_permission(context, Permission::new(&node, &description, default)).await
+                {
+                    registered_permissions.push(node.clone());
+                }
+
+                match &cmd_data.permission_message {
+                    Some(message) => (
+                        open_permission,
+                        Some(CommandPermission {
+                            node,
+                            message: message.replace("<permission>", declared),
+                        }),
+                    ),
+                    None => (node, None),
+                }
+            }
+            None => (open_permission, None),
+        };
 
-        Ok(())
+        let labels: Vec<String> = std::iter::once(cmd_name.clone()).chain(aliases).collect();
+        for label in &labels {
+            let node = init_java_command(
+                cmd_name.clone(),
+                label.clone(),
+                plugin.name.clone(),
+                command_tx.clone(),
+                tab_completer.clone(),
+                cmd_data.description.clone().unwrap_or_default(),
+                cmd_data.usage.clone(),
+                executor_permission.clone(),
+            );
+            context.register_command(node, permission.clone()).await;
+        }
+
+        Ok((labels, registered_permissions))
     }
 
     pub fn trigger_command(
@@ -210,7 +319,7 @@ impl CommandManager {
         jvm: &Jvm,
         full_command: String,
         sender: SimpleCommandSender,
-    ) -> Result<()> {
+    ) -> Result<CommandOutcome> {
         let command_map = match self.command_map {
             Some(ref command_map) => command_map,
             None => match self.init(jvm) {
@@ -219,7 +328,34 @@ impl CommandManager {
             },
         };
 
+        // `dispatch` also returns false for an unknown label, which is not a usage error
+        let label = full_command
+            .trim_start_matches('/')
+            .split(' ')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let command = jvm.invoke(
+            command_map,
+            "getCommand",
+            &[InvocationArg::try_from(label)?],
+        )?;
+        let unknown = jvm.invoke_static(
+            "java.util.Objects",
+            "isNull",
+            &[InvocationArg::from(command)],
+        )?;
+        if jvm.to_rust::<bool>(unknown)? {
+            return Ok(CommandOutcome {
+                found: false,
+                success: false,
+                output: Vec::new(),
+            });
+        }
+
+        let captures_output = matches!(sender, SimpleCommandSender::Rcon);
         let j_sender = Self::sender_to_jsender(jvm, sender)?;
+        let j_sender_for_output = jvm.clone_instance(&j_sender)?;
 
         let dispatch_result = jvm.invoke(
             command_map,
@@ -230,13 +366,20 @@ impl CommandManager {
             ],
         )?;
 
-        let handled: bool = jvm.to_rust(dispatch_result)?;
+        let success: bool = jvm.to_rust(dispatch_result)?;
 
-        if !handled {
-            //log::warn!("Command was not handled by any Java plugin: {}", cmd_name);
-        }
+        let output = if captures_output {
+            let output = jvm.invoke(&j_sender_for_output, "drainOutput", InvocationArg::empty())?;
+            jvm.to_rust(output)?
+        } else {
+            Vec::new()
+        };
 
-        Ok(())
+        Ok(CommandOutcome {
+            found: true,
+            success,
+            output,
+        })
     }
 
     pub fn sender_to_jsender(jvm: &Jvm, sender: SimpleCommandSender) -> Result<Instance> {
@@ -260,6 +403,108 @@ impl CommandManager {
 
                 Ok(jvm.invoke(&patch_server, "getPlayer", &[InvocationArg::from(j_uuid)])?)
             }
+
+            SimpleCommandSender::Rcon => Ok(jvm.create_instance(
+                "org.patchbukkit.command.PatchBukkitRemoteConsoleCommandSender",
+                InvocationArg::empty(),
+            )?),
+
+            SimpleCommandSender::CommandBlock { world, position } => {
+                let j_world = jvm.invoke_static(
+                    "org.patchbukkit.world.PatchBukkitWorld",
+                    "getOrCreate",
+                    &[InvocationArg::try_from(world.to_string())?],
+                )?;
+
+                Ok(jvm.create_instance(
+                    "org.patchbukkit.command.PatchBukkitBlockCommandSender",
+                    &[
+                        InvocationArg::from(j_world),
+                        InvocationArg::try_from(position.0.x)?.into_primitive()?,
+                        InvocationArg::try_from(position.0.y)?.into_primitive()?,
+                        InvocationArg::try_from(position.0.z)?.into_primitive()?,
+                    ],
+                )?)
+            }
+        }
+    }
+}
+
+/// The greedy argument Pumpkin hands to a Java command: everything after the label and the
+/// space following it, with repeated and trailing spaces kept as Bukkit keeps them.
+fn command_argument(full_command: &str) -> &str {
+    let command = full_command.trim_start_matches('/').trim_start();
+    match command.find(' ') {
+        Some(label_end) => &command[label_end + 1..],
+        None => "",
+    }
+}
+
+/// The Pumpkin suggestion for a Bukkit `completion` of the word being typed in `full_command`.
+///
+/// Pumpkin's `CommandSuggestion` carries no replacement range, so the client replaces the
+/// whole greedy argument with it. The words before the one being completed are therefore
+/// repeated in front of the completion: the client lists and highlights `set spawn` rather
+/// than `spawn`, but the command line it produces is the one Bukkit would.
+fn suggestion_text(full_command: &str, completion: &str) -> String {
+    let argument = command_argument(full_command);
+    let word_start = argument.rfind(' ').map_or(0, |i| i + 1);
+    format!("{}{}", &argument[..word_start], completion)
+}
+
+/// Normalized `aliases` of a `plugin.yml` command, without duplicates or the command name itself.
+fn command_aliases(cmd_name: &str, cmd_data: &config::spigot::Command) -> Vec<String> {
+    let cmd_name = cmd_name.trim().to_lowercase();
+    let mut aliases: Vec<String> = Vec::new();
+    for alias in cmd_data
+        .aliases
+        .as_ref()
+        .map(|aliases| aliases.to_vec())
+        .unwrap_or_default()
+    {
+        let alias = alias.trim().to_lowercase();
+        if alias.is_empty() || alias == cmd_name || aliases.contains(&alias) {
+            continue;
         }
+        aliases.push(alias);
+    }
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_the_only_word() {
+        assert_eq!(suggestion_text("/warp", "spawn"), "spawn");
+        assert_eq!(suggestion_text("/warp ", "spawn"), "spawn");
+        assert_eq!(suggestion_text("/warp sp", "spawn"), "spawn");
+    }
+
+    #[test]
+    fn keeps_earlier_words() {
+        assert_eq!(suggestion_text("/warp set sp", "spawn"), "set spawn");
+        assert_eq!(suggestion_text("warp set sp", "spawn"), "set spawn");
+    }
+
+    #[test]
+    fn leading_spaces() {
+        assert_eq!(suggestion_text("/ warp set sp", "spawn"), "set spawn");
+        assert_eq!(suggestion_text("/warp  sp", "spawn"), " spawn");
+    }
+
+    #[test]
+    fn trailing_spaces() {
+        assert_eq!(suggestion_text("/warp set ", "spawn"), "set spawn");
+        assert_eq!(suggestion_text("/warp set  ", "spawn"), "set  spawn");
+    }
+
+    #[test]
+    fn repeated_spaces() {
+        assert_eq!(
+            suggestion_text("/warp set   home sp", "spawn"),
+            "set   home spawn"
+        );
     }
 }
@@ -0,0 +1,272 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+pub const DEPENDENCY_REPORT_FILE: &str = "dependency-report.json";
+
+/// Dependency problems found while ordering the plugins
+#[derive(Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct DependencyReport {
+    pub missing_dependencies: Vec<MissingDependencies>,
+    pub version_mismatches: Vec<VersionMismatch>,
+    /// Plugins that depend on each other, one entry per cycle
+    pub cycles: Vec<Vec<String>>,
+    pub provides_collisions: Vec<ProvidesCollision>,
+    pub skipped_soft_dependencies: Vec<SkippedSoftDependency>,
+}
+
+/// Hard dependencies that are not installed
+#[derive(Serialize, Debug, Clone)]
+pub struct MissingDependencies {
+    pub plugin: String,
+    pub missing: Vec<String>,
+}
+
+/// A dependency installed in a version outside the declared range
+#[derive(Serialize, Debug, Clone)]
+pub struct VersionMismatch {
+    pub plugin: String,
+    pub dependency: String,
+    pub required: String,
+    pub found: String,
+}
+
+/// A name claimed by several plugins, through their own name or `provides`
+#[derive(Serialize, Debug, Clone)]
+pub struct ProvidesCollision {
+    pub name: String,
+    pub providers: Vec<String>,
+    /// The plugin that dependencies on `name` resolve to
+    pub resolved_to: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SkippedSoftDependency {
+    pub plugin: String,
+    pub dependency: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// No installed plugin has or provides this name
+    NotInstalled,
+    /// The dependency is installed but will not be enabled
+    Errored,
+    /// The installed dependency is outside the declared version range
+    VersionMismatch,
+}
+
+impl DependencyReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing_dependencies.is_empty()
+            && self.version_mismatches.is_empty()
+            && self.cycles.is_empty()
+            && self.provides_collisions.is_empty()
+            && self.skipped_soft_dependencies.is_empty()
+    }
+
+    /// Prints the report to the server log
+    pub fn log(&self) {
+        if self.is_empty() {
+            log::info!("No plugin dependency problems found");
+            return;
+        }
+
+        log::warn!("Plugin dependency report:");
+        for entry in &self.missing_dependencies {
+            log::warn!(
+                "  {} is missing required dependencies: {}",
+                entry.plugin,
+                entry.missing.join(", ")
+            );
+        }
+        for mismatch in &self.version_mismatches {
+            log::warn!(
+                "  {} requires {} {}, found {}",
+                mismatch.plugin,
+                mismatch.dependency,
+                mismatch.required,
+                mismatch.found
+            );
+        }
+        for cycle in &self.cycles {
+            log::warn!("  Dependency cycle between: {}", cycle.join(", "));
+        }
+        for collision in &self.provides_collisions {
+            log::warn!(
+                "  {} is provided by {} (using {})",
+                collision.name,
+                collision.providers.join(", "),
+                collision.resolved_to
+            );
+        }
+        for skipped in &self.skipped_soft_dependencies {
+            let reason = match skipped.reason {
+                SkipReason::NotInstalled => "not installed",
+                SkipReason::Errored => "errored",
+                SkipReason::VersionMismatch => "version mismatch",
+            };
+            log::warn!(
+                "  {} runs without its soft dependency {} ({})",
+                skipped.plugin,
+                skipped.dependency,
+                reason
+            );
+        }
+    }
+
+    /// Writes the report as JSON into the PatchBukkit data folder
+    pub fn write(&self, data_folder: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(data_folder.join(DEPENDENCY_REPORT_FILE), json)?;
+        Ok(())
+    }
+}
+
+/// Finds the dependency cycles among `nodes` with Tarjan's strongly connected components.
+///
+/// Each cycle is sorted by name, and the cycles are sorted by their first member.
+pub fn find_cycles(nodes: &[String], edges: &HashMap<String, HashSet<String>>) -> Vec<Vec<String>> {
+    struct Tarjan<'a> {
+        nodes: &'a HashSet<&'a str>,
+        edges: &'a HashMap<String, HashSet<String>>,
+        index: usize,
+        indices: HashMap<&'a str, usize>,
+        low_links: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        components: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &'a str) {
+            self.indices.insert(node, self.index);
+            self.low_links.insert(node, self.index);
+            self.index += 1;
+            self.stack.push(node);
+            self.on_stack.insert(node);
+
+            let (nodes, edges) = (self.nodes, self.edges);
+            if let Some(children) = edges.get(node) {
+                for child in children {
+                    let Some(&child) = nodes.get(child.as_str()) else {
+                        continue;
+                    };
+                    if !self.indices.contains_key(child) {
+                        self.visit(child);
+                        let low = self.low_links[node].min(self.low_links[child]);
+                        self.low_links.insert(node, low);
+                    } else if self.on_stack.contains(child) {
+                        let low = self.low_links[node].min(self.indices[child]);
+                        self.low_links.insert(node, low);
+                    }
+                }
+            }
+
+            if self.low_links[node] == self.indices[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(member);
+                    component.push(member.to_string());
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let node_set: HashSet<&str> = nodes.iter().map(String::as_str).collect();
+    let mut sorted_nodes: Vec<&str> = node_set.iter().copied().collect();
+    sorted_nodes.sort();
+
+    let mut tarjan = Tarjan {
+        nodes: &node_set,
+        edges,
+        index: 0,
+        indices: HashMap::new(),
+        low_links: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for node in sorted_nodes {
+        if !tarjan.indices.contains_key(node) {
+            tarjan.visit(node);
+        }
+    }
+
+    // Single plugins are never cycles, add_edge drops self edges
+    let mut cycles: Vec<Vec<String>> = tarjan
+        .components
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|mut component| {
+            component.sort();
+            component
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str)]) -> HashMap<String, HashSet<String>> {
+        let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+        for (from, to) in edges {
+            graph
+                .entry(from.to_string())
+                .or_default()
+                .insert(to.to_string());
+        }
+        graph
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_each_cycle_once() {
+        let edges = graph(&[
+            ("b", "a"),
+            ("a", "b"),
+            ("e", "c"),
+            ("c", "d"),
+            ("d", "e"),
+            ("f", "a"),
+        ]);
+        assert_eq!(
+            find_cycles(&names(&["f", "e", "d", "c", "b", "a"]), &edges),
+            [names(&["a", "b"]), names(&["c", "d", "e"])]
+        );
+    }
+
+    #[test]
+    fn no_cycles() {
+        let edges = graph(&[("a", "b"), ("b", "c"), ("a", "c")]);
+        assert!(find_cycles(&names(&["a", "b", "c"]), &edges).is_empty());
+    }
+
+    #[test]
+    fn ignores_plugins_outside_the_nodes() {
+        let edges = graph(&[("a", "b"), ("b", "a"), ("b", "c"), ("c", "b")]);
+        assert_eq!(
+            find_cycles(&names(&["b", "c"]), &edges),
+            [names(&["b", "c"])]
+        );
+        assert!(find_cycles(&names(&["a"]), &edges).is_empty());
+    }
+}
@@ -1,20 +1,43 @@
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    fs,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use j4rs::{Instance, InvocationArg, Jvm};
 use pumpkin::plugin::Context;
 use tokio::sync::mpsc;
 
 use crate::{
     config::{
-        paper::PaperPluginYml,
-        spigot::{Command, SpigotPluginYml},
+        error::PluginConfigError,
+        paper::{
+            LoadOrder as PaperLoadOrder, PAPER_PLUGIN_CONFIG, PaperDependency, PaperPluginYml,
+        },
+        patchbukkit::PluginsConfig,
+        spigot::{Command, LoadOrder, SPIGOT_PLUGIN_CONFIG, SpigotPluginYml},
+        version::{IncompatibleApiVersion, Version, VersionRange, check_api_version},
+    },
+    events::handler::ListenerHandle,
+    java::{
+        jar::read_configs_from_jar,
+        jvm::{
+            commands::{EventListenerInfo, JvmCommand, LoadPluginResult, PluginInfo},
+            watchdog,
+        },
+        native_callbacks::events::take_registered_listeners,
+        plugin::{
+            command_manager::CommandManager,
+            dependency_report::{
+                DependencyReport, MissingDependencies, ProvidesCollision, SkipReason,
+                SkippedSoftDependency, VersionMismatch, find_cycles,
+            },
+            permission_manager::{register_plugin_permissions, unregister_permission},
+        },
     },
-    java::{jvm::commands::JvmCommand, plugin::command_manager::CommandManager},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,6 +52,8 @@ pub enum PluginState {
     Disabled,
     /// Plugin failed to load or enable
     Errored,
+    /// Another jar declaring the same plugin name was loaded instead
+    Skipped,
 }
 
 #[derive(Debug)]
@@ -55,6 +80,8 @@ pub struct Plugin {
     pub version: String,
     /// Main class fully qualified name
     pub main_class: String,
+    /// Whether the plugin is enabled before or after the worlds are loaded
+    pub load: LoadOrder,
     /// Path to the JAR file
     pub path: PathBuf,
     /// Plugin-specific data (Paper or Spigot)
@@ -64,10 +91,15 @@ pub struct Plugin {
     /// Data folder for this plugin
     pub data_folder: PathBuf,
     pub instance: Option<Instance>,
+    /// Whether the Paper bootstrapper and loader already ran for this jar
+    pub bootstrapped: bool,
     // The registered commands
     pub commands: HashMap<String, Command>,
 
-    pub listeners: HashMap<String, Instance>,
+    // What the plugin registered with Pumpkin, torn down when it is disabled
+    pub listeners: Vec<ListenerHandle>,
+    pub registered_commands: Vec<String>,
+    pub registered_permissions: Vec<String>,
 
     // Dependency metadata (normalized to lowercase)
     pub provides: Vec<String>,
@@ -76,32 +108,179 @@ pub struct Plugin {
     pub load_before: Vec<String>,
     pub load_after: Vec<String>,
     pub classpath_deps: Vec<String>,
+    /// Version requirements on dependencies, keyed by normalized name
+    pub dependency_versions: HashMap<String, VersionRange>,
 
     // Library coordinates (original casing preserved)
     pub libraries: Vec<String>,
 }
 
+impl Plugin {
+    /// The parsed `plugin.yml`, if the plugin ships one
+    pub fn spigot_config(&self) -> Option<&SpigotPluginYml> {
+        match &self.plugin_type {
+            PluginType::Paper(data) => data.spigot_config.as_ref(),
+            PluginType::Spigot(data) => Some(&data.spigot_config),
+        }
+    }
+
+    /// A snapshot of the plugin for the `/patchbukkit` command
+    pub fn info(&self) -> PluginInfo {
+        let authors = match &self.plugin_type {
+            PluginType::Paper(data) => data.paper_config.get_all_authors(),
+            PluginType::Spigot(data) => data.spigot_config.get_all_authors(),
+        };
+        let mut commands: Vec<String> = self.commands.keys().cloned().collect();
+        commands.sort();
+        let mut events: Vec<String> = self
+            .listeners
+            .iter()
+            .map(|listener| listener.event_type.clone())
+            .collect();
+        events.sort();
+        events.dedup();
+
+        PluginInfo {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            state: self.state,
+            authors,
+            depends: self.depends.clone(),
+            soft_depends: self.soft_depends.clone(),
+            provides: self.provides.clone(),
+            commands,
+            events,
+        }
+    }
+}
+
 pub struct PluginManager {
     pub plugins: HashMap<String, Plugin>,
+    /// Keys of the instantiated plugins, dependencies first
+    load_order: Vec<String>,
+    /// Problems found while computing `load_order`
+    dependency_report: DependencyReport,
+    /// Plugins that lost a name collision to a plugin in `plugins`
+    skipped: Vec<Plugin>,
+    /// Jar file name to prefer, by normalized plugin name, when names collide
+    preferred_jars: HashMap<String, String>,
+}
+
+/// A plugin jar that was not loaded because another jar declares the same plugin name
+#[derive(Debug)]
+pub struct DuplicatePlugin {
+    pub name: String,
+    pub kept: PathBuf,
 }
 
+impl std::fmt::Display for DuplicatePlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is already provided by `{}`",
+            self.name,
+            self.kept.display()
+        )
+    }
+}
+
+impl std::error::Error for DuplicatePlugin {}
+
 impl PluginManager {
     pub fn new() -> Self {
         Self {
             plugins: HashMap::new(),
+            load_order: Vec::new(),
+            dependency_report: DependencyReport::default(),
+            skipped: Vec::new(),
+            preferred_jars: HashMap::new(),
         }
     }
 
-    pub fn add_plugin(&mut self, plugin: Plugin) {
+    pub fn configure(&mut self, config: &PluginsConfig) {
+        self.preferred_jars = config
+            .prefer_jars
+            .iter()
+            .map(|(name, jar)| (normalize_name(name), jar.clone()))
+            .collect();
+    }
+
+    /// Registers a plugin, resolving name collisions independently of discovery order.
+    ///
+    /// A jar preferred in patchbukkit.yml wins, then the highest version, then the jar
+    /// whose path sorts first. A plugin that is already running is never replaced.
+    /// The losing plugin is kept in the `Skipped` state.
+    pub fn add_plugin(&mut self, plugin: Plugin) -> Result<(), DuplicatePlugin> {
         let key = normalize_name(&plugin.name);
-        if self.plugins.contains_key(&key) {
-            log::warn!(
-                "Duplicate plugin name detected ({}). Keeping the first instance.",
-                plugin.name
-            );
-            return;
+        let Some(existing) = self.plugins.get(&key) else {
+            self.plugins.insert(key, plugin);
+            return Ok(());
+        };
+
+        let (keep_existing, reason) = if existing.instance.is_some() {
+            (true, "it is already running")
+        } else {
+            match self.duplicate_preference(&key, existing, &plugin) {
+                (Ordering::Less, reason) => (false, reason),
+                (_, reason) => (true, reason),
+            }
+        };
+
+        let (kept, mut skipped) = if keep_existing {
+            (existing.path.clone(), plugin)
+        } else {
+            let existing = self
+                .plugins
+                .insert(key.clone(), plugin)
+                .expect("checked above");
+            (self.plugins[&key].path.clone(), existing)
+        };
+        log::warn!(
+            "Duplicate plugin {} in `{}` and `{}`. Loading `{}` because {}",
+            skipped.name,
+            kept.display(),
+            skipped.path.display(),
+            kept.display(),
+            reason
+        );
+
+        skipped.state = PluginState::Skipped;
+        let result = if keep_existing {
+            Err(DuplicatePlugin {
+                name: skipped.name.clone(),
+                kept,
+            })
+        } else {
+            Ok(())
+        };
+        self.skipped.push(skipped);
+        result
+    }
+
+    /// Compares two plugins declaring the same name, `Ordering::Greater` meaning `a` wins
+    fn duplicate_preference(&self, key: &str, a: &Plugin, b: &Plugin) -> (Ordering, &'static str) {
+        if let Some(preferred) = self.preferred_jars.get(key) {
+            let is_preferred = |plugin: &Plugin| {
+                plugin
+                    .path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy() == preferred.as_str())
+            };
+            match (is_preferred(a), is_preferred(b)) {
+                (true, false) => return (Ordering::Greater, "it is preferred in patchbukkit.yml"),
+                (false, true) => return (Ordering::Less, "it is preferred in patchbukkit.yml"),
+                _ => {}
+            }
         }
-        self.plugins.insert(key, plugin);
+
+        let versions = Version::parse(&a.version).cmp(&Version::parse(&b.version));
+        if versions.is_ne() {
+            return (versions, "it has the higher version");
+        }
+        (
+            b.path.cmp(&a.path),
+            "both have the same version and its path sorts first",
+        )
     }
 
     pub fn load_paper_plugin<P: AsRef<Path>>(
@@ -110,11 +289,21 @@ impl PluginManager {
         paper_plugin_config: &str,
         spigot_plugin_config: &Option<String>,
     ) -> Result<()> {
-        let parsed_paper_plugin = PaperPluginYml::from_str(paper_plugin_config)?;
+        let parsed_paper_plugin = PaperPluginYml::from_str(paper_plugin_config)
+            .map_err(|e| PluginConfigError::new(PAPER_PLUGIN_CONFIG, paper_plugin_config, e))?;
         let parsed_spigot_plugin = match spigot_plugin_config {
-            Some(config) => Some(SpigotPluginYml::from_str(config)?),
+            Some(config) => Some(
+                SpigotPluginYml::from_str(config)
+                    .map_err(|e| PluginConfigError::new(SPIGOT_PLUGIN_CONFIG, config, e))?,
+            ),
             None => None,
         };
+        let api_version = parsed_paper_plugin.api_version.as_deref().or_else(|| {
+            parsed_spigot_plugin
+                .as_ref()
+                .and_then(|spigot| spigot.api_version.as_deref())
+        });
+        check_api_version(&parsed_paper_plugin.name, api_version)?;
 
         let (spigot_depends, spigot_soft_depends, spigot_load_before, spigot_load_after, spigot_provides, spigot_libraries, spigot_skip_libraries) = match &parsed_spigot_plugin {
             Some(config) => (
@@ -134,6 +323,7 @@ impl PluginManager {
         let mut paper_load_before = Vec::new();
         let mut paper_load_after = Vec::new();
         let mut paper_classpath = Vec::new();
+        let mut dependency_versions = HashMap::new();
 
         if let Some(ref deps) = parsed_paper_plugin.dependencies {
             for (name, dep) in deps
@@ -158,6 +348,13 @@ impl PluginManager {
                     crate::config::paper::LoadOrder::Omit => {}
                 }
 
+                if let Some(ref range) = dep.version {
+                    let range: VersionRange = range.parse().map_err(|e| {
+                        anyhow!("Invalid version range for dependency {}: {}", name, e)
+                    })?;
+                    dependency_versions.insert(normalized.clone(), range);
+                }
+
                 if dep.join_classpath {
                     paper_classpath.push(normalized);
                 }
@@ -193,8 +390,18 @@ impl PluginManager {
         };
 
         let name = parsed_paper_plugin.name.clone();
+        let data_folder = data_folder_for(jar_path.as_ref(), &name)?;
         let version = parsed_paper_plugin.version.clone();
         let main_class = parsed_paper_plugin.main.clone();
+        let load = parsed_paper_plugin
+            .load
+            .clone()
+            .or_else(|| {
+                parsed_spigot_plugin
+                    .as_ref()
+                    .and_then(|spigot| spigot.load.clone())
+            })
+            .unwrap_or_default();
         let paper_config = parsed_paper_plugin;
         let spigot_config = parsed_spigot_plugin;
 
@@ -202,16 +409,20 @@ impl PluginManager {
             name,
             version,
             main_class,
+            load,
             plugin_type: PluginType::Paper(PaperPluginData {
                 paper_config,
                 spigot_config,
             }),
             state: PluginState::Registered,
-            data_folder: jar_path.as_ref().parent().unwrap().join("data"),
+            data_folder,
             path: jar_path.as_ref().to_path_buf(),
             instance: None,
+            bootstrapped: false,
             commands,
-            listeners: HashMap::new(),
+            listeners: Vec::new(),
+            registered_commands: Vec::new(),
+            registered_permissions: Vec::new(),
 
             provides,
             depends: dedupe_names(depends),
@@ -219,10 +430,11 @@ impl PluginManager {
             load_before: dedupe_names(load_before),
             load_after: dedupe_names(load_after),
             classpath_deps: dedupe_names(classpath_deps),
+            dependency_versions,
             libraries: dedupe_strings(libraries),
         };
 
-        self.add_plugin(plugin);
+        self.add_plugin(plugin)?;
         Ok(())
     }
 
@@ -231,7 +443,12 @@ impl PluginManager {
         jar_path: P,
         spigot_plugin_config: &str,
     ) -> Result<()> {
-        let parsed_spigot_plugin = SpigotPluginYml::from_str(spigot_plugin_config)?;
+        let parsed_spigot_plugin = SpigotPluginYml::from_str(spigot_plugin_config)
+            .map_err(|e| PluginConfigError::new(SPIGOT_PLUGIN_CONFIG, spigot_plugin_config, e))?;
+        check_api_version(
+            &parsed_spigot_plugin.name,
+            parsed_spigot_plugin.api_version.as_deref(),
+        )?;
 
         let depends = normalize_names(parsed_spigot_plugin.depend.clone());
         let soft_depends = normalize_names(parsed_spigot_plugin.softdepend.clone());
@@ -244,8 +461,10 @@ impl PluginManager {
         };
 
         let name = parsed_spigot_plugin.name.clone();
+        let data_folder = data_folder_for(jar_path.as_ref(), &name)?;
         let version = parsed_spigot_plugin.version.clone();
         let main_class = parsed_spigot_plugin.main.clone();
+        let load = parsed_spigot_plugin.load.clone().unwrap_or_default();
         let spigot_config = parsed_spigot_plugin.clone();
         let commands = parsed_spigot_plugin.commands.clone().unwrap_or_default();
 
@@ -253,13 +472,17 @@ impl PluginManager {
             name,
             version,
             main_class,
+            load,
             plugin_type: PluginType::Spigot(SpigotPluginData { spigot_config }),
             state: PluginState::Registered,
-            data_folder: jar_path.as_ref().parent().unwrap().join("data"),
+            data_folder,
             path: jar_path.as_ref().to_path_buf(),
             instance: None,
+            bootstrapped: false,
             commands,
-            listeners: HashMap::new(),
+            listeners: Vec::new(),
+            registered_commands: Vec::new(),
+            registered_permissions: Vec::new(),
 
             provides,
             depends: dedupe_names(depends),
@@ -267,247 +490,932 @@ impl PluginManager {
             load_before: dedupe_names(load_before),
             load_after: Vec::new(),
             classpath_deps: Vec::new(),
+            dependency_versions: HashMap::new(),
             libraries: dedupe_strings(libraries),
         };
 
-        self.add_plugin(plugin);
+        self.add_plugin(plugin)?;
         Ok(())
     }
 
-    pub fn enable_all_plugins(&mut self, jvm: &Jvm) -> Result<()> {
+    /// Enables the plugins of one `load` phase in load order, so dependencies are enabled
+    /// before their dependents
+    pub async fn enable_all_plugins(
+        &mut self,
+        jvm: &Jvm,
+        context: &Arc<Context>,
+        load: LoadOrder,
+    ) -> Result<()> {
+        if load == LoadOrder::Startup {
+            self.warn_postworld_dependencies();
+        }
+
         // IMPORANT: enable trough PluginManager not manually
-        let plugin_manager = jvm.invoke_static(
-            "org.bukkit.Bukkit",
-            "getPluginManager",
-            InvocationArg::empty(),
-        )?;
-        for (_plugin_name, plugin) in &mut self.plugins {
-            if plugin.instance.is_none() {
+        let plugin_manager = bukkit_plugin_manager(jvm)?;
+        for plugin_key in self.load_order.clone() {
+            let Some(plugin) = self.plugins.get_mut(&plugin_key) else {
+                continue;
+            };
+            if plugin.load != load || plugin.state == PluginState::Errored {
+                // Errored here means a dependency failed to enable before it
                 continue;
             }
-            let plugin_instance = plugin.instance.as_ref().unwrap();
-            let plugin_instance = jvm.clone_instance(&plugin_instance).unwrap();
-
-            let result = jvm.invoke(
-                &plugin_manager,
-                "enablePlugin",
-                &[InvocationArg::from(plugin_instance)],
-            );
 
-            match result {
-                Ok(_) => {
-                    plugin.state = PluginState::Enabled;
-                    log::info!("Enabled PatchBukkit plugin: {}", plugin.name);
-                }
-                Err(e) => {
-                    plugin.state = PluginState::Errored;
-                    log::error!(
-                        "Failed to enable PatchBukkit plugin {}: {:?}",
-                        plugin.name,
-                        e
-                    );
-                }
+            enable_plugin(jvm, &plugin_manager, context, plugin).await;
+            if plugin.state == PluginState::Errored {
+                let root_cause = format!("{} failed to enable", plugin.name);
+                self.cascade_errored(&plugin_key, &root_cause);
             }
         }
         Ok(())
     }
 
-    pub fn disable_all_plugins(&mut self, jvm: &Jvm) -> Result<()> {
-        let plugin_manager = jvm.invoke_static(
-            "org.bukkit.Bukkit",
-            "getPluginManager",
-            InvocationArg::empty(),
-        )?;
-        for (_plugin_name, plugin) in &mut self.plugins {
-            if plugin.instance.is_none() {
-                continue;
+    /// Disables plugins in reverse load order, so dependents are disabled before their dependencies
+    pub async fn disable_all_plugins(&mut self, jvm: &Jvm, context: &Arc<Context>) -> Result<()> {
+        let plugin_manager = bukkit_plugin_manager(jvm)?;
+        for plugin_key in self.load_order.iter().rev() {
+            if let Some(plugin) = self.plugins.get_mut(plugin_key) {
+                disable_plugin(jvm, &plugin_manager, context, plugin).await;
             }
-            let plugin_instance = plugin.instance.as_ref().unwrap();
-            let plugin_instance = jvm.clone_instance(&plugin_instance).unwrap();
+        }
+        Ok(())
+    }
 
-            let result = jvm.invoke(
-                &plugin_manager,
-                "disablePlugin",
-                &[InvocationArg::from(plugin_instance)],
-            );
+    /// The plugin owning the command `full_command` runs, by name or alias
+    pub fn command_owner(&self, full_command: &str) -> Option<String> {
+        let label = full_command
+            .trim_start_matches('/')
+            .split_whitespace()
+            .next()?
+            .to_lowercase();
+        self.plugins
+            .values()
+            .find(|plugin| {
+                plugin.commands.iter().any(|(name, command)| {
+                    name.to_lowercase() == label
+                        || command.aliases.as_ref().is_some_and(|aliases| {
+                            aliases
+                                .to_vec()
+                                .iter()
+                                .any(|alias| alias.to_lowercase() == label)
+                        })
+                })
+            })
+            .map(|plugin| plugin.name.clone())
+    }
 
-            match result {
-                Ok(_) => {
-                    plugin.state = PluginState::Disabled;
-                    log::info!("Disabled PatchBukkit plugin: {}", plugin.name);
-                }
-                Err(e) => {
-                    plugin.state = PluginState::Disabled;
-                    log::error!(
-                        "Failed to disable PatchBukkit plugin {}: {:?}",
-                        plugin.name,
-                        e
-                    );
-                }
-            }
+    /// Snapshots of every plugin, sorted by name
+    pub fn plugin_infos(&mut self) -> Vec<PluginInfo> {
+        let mut infos: Vec<PluginInfo> = self
+            .plugins
+            .values_mut()
+            .map(|plugin| {
+                claim_listeners(plugin);
+                plugin.info()
+            })
+            .collect();
+        infos.extend(self.skipped.iter().map(Plugin::info));
+        infos.sort_by_key(|info| info.name.to_lowercase());
+        infos
+    }
+
+    pub fn plugin_info(&mut self, name: &str) -> Option<PluginInfo> {
+        let plugin = self.plugins.get_mut(&normalize_name(name))?;
+        claim_listeners(plugin);
+        Some(plugin.info())
+    }
+
+    /// The Bukkit listeners of every instantiated plugin, in load order, as Java's handler
+    /// lists know them
+    pub fn event_listeners(&mut self, jvm: &Jvm) -> Result<Vec<(String, Vec<EventListenerInfo>)>> {
+        let mut listeners = Vec::new();
+        for plugin_key in &self.load_order {
+            let Some(plugin) = self.plugins.get_mut(plugin_key) else {
+                continue;
+            };
+            let Some(plugin_instance) = &plugin.instance else {
+                continue;
+            };
+            let lines = jvm.invoke_static(
+                "org.patchbukkit.events.PatchBukkitEventManager",
+                "describeListeners",
+                &[InvocationArg::from(jvm.clone_instance(plugin_instance)?)],
+            )?;
+            let lines: Vec<String> = jvm.to_rust(lines)?;
+
+            claim_listeners(plugin);
+            let plugin_listeners = lines
+                .iter()
+                .filter_map(|line| {
+                    let mut parts = line.split(' ');
+                    let event = parts.next()?.to_string();
+                    Some(EventListenerInfo {
+                        forwarded: plugin
+                            .listeners
+                            .iter()
+                            .any(|listener| listener.event_type == event),
+                        event,
+                        priority: parts.next()?.to_string(),
+                        ignore_cancelled: parts.next()? == "true",
+                        listener: parts.next()?.to_string(),
+                    })
+                })
+                .collect();
+            listeners.push((plugin.name.clone(), plugin_listeners));
         }
-        Ok(())
+        Ok(listeners)
     }
 
-    pub async fn instantiate_all_plugins(
+    /// Enables a single plugin, registering its commands again if it was disabled
+    pub async fn enable_plugin_by_name(
         &mut self,
         jvm: &Jvm,
-        server: &Arc<Context>,
+        context: &Arc<Context>,
         command_tx: mpsc::Sender<JvmCommand>,
         command_manager: &mut CommandManager,
+        name: &str,
     ) -> Result<()> {
-        let load_order = self.compute_load_order();
-
-        for plugin_key in load_order {
-            let (classpath, libraries) = match self.plugins.get(&plugin_key) {
-                Some(plugin) => (
-                    self.classpath_string_for(plugin),
-                    self.library_string_for(plugin),
-                ),
-                None => continue,
-            };
-
-            let plugin = match self.plugins.get_mut(&plugin_key) {
-                Some(plugin) => plugin,
-                None => continue,
-            };
-            let plugin_instance = jvm.invoke_static(
-                "org.patchbukkit.loader.PatchBukkitPluginLoader",
-                "createPlugin",
-                &[
-                    InvocationArg::try_from(&plugin.path.to_string_lossy().to_string())?,
-                    InvocationArg::try_from(&plugin.main_class)?,
-                    InvocationArg::try_from(&classpath)?,
-                    InvocationArg::try_from(&libraries)?,
-                ],
-            )?;
-
-            plugin.instance = Some(plugin_instance);
-            for (cmd_name, cmd_data) in &plugin.commands {
-                match command_manager
-                    .register_command(
-                        &jvm,
-                        server,
-                        &plugin,
-                        cmd_name.clone(),
-                        cmd_data,
-                        command_tx.clone(),
-                    )
-                    .await
-                {
-                    Ok(_) => (),
-                    Err(e) => {
-                        log::error!(
-                            "Failed to register command {} for plugin {}: {:?}",
-                            cmd_name,
-                            plugin.name,
-                            e
-                        );
-                    }
-                }
+        let plugin = self
+            .plugins
+            .get_mut(&normalize_name(name))
+            .ok_or_else(|| anyhow!("No PatchBukkit plugin named {}", name))?;
+
+        match plugin.state {
+            PluginState::Enabled => return Err(anyhow!("{} is already enabled", plugin.name)),
+            _ if plugin.instance.is_none() => {
+                return Err(anyhow!(
+                    "{} was never instantiated, reload it instead",
+                    plugin.name
+                ));
+            }
+            PluginState::Disabled => {
+                register_with_pumpkin(jvm, context, command_tx, command_manager, plugin).await
             }
+            _ => {}
+        }
 
-            plugin.state = PluginState::Loaded;
-            log::info!("Loaded and registered commands for: {}", plugin.name);
+        enable_plugin(jvm, &bukkit_plugin_manager(jvm)?, context, plugin).await;
+        match plugin.state {
+            PluginState::Enabled => Ok(()),
+            _ => Err(anyhow!("{} failed to enable", plugin.name)),
         }
-        Ok(())
     }
 
-    pub fn unload_all_plugins(&mut self) -> Result<()> {
-        self.plugins.clear();
+    /// Disables a single enabled plugin and unregisters it from Pumpkin
+    pub async fn disable_plugin_by_name(
+        &mut self,
+        jvm: &Jvm,
+        context: &Arc<Context>,
+        name: &str,
+    ) -> Result<()> {
+        let plugin = self
+            .plugins
+            .get_mut(&normalize_name(name))
+            .ok_or_else(|| anyhow!("No PatchBukkit plugin named {}", name))?;
+        if plugin.state != PluginState::Enabled {
+            return Err(anyhow!("{} is not enabled", plugin.name));
+        }
+
+        disable_plugin(jvm, &bukkit_plugin_manager(jvm)?, context, plugin).await;
         Ok(())
     }
 
-    fn resolve_dependency_name(
-        &self,
+    /// Reads a plugin jar and registers the plugin it contains
+    pub fn load_plugin(&mut self, plugin_path: &Path) -> LoadPluginResult {
+        match read_configs_from_jar(plugin_path) {
+            Ok((Some(paper_plugin_config), spigot)) => {
+                match self.load_paper_plugin(plugin_path, &paper_plugin_config, &spigot) {
+                    Ok(_) => LoadPluginResult::SuccessfullyLoadedPaper,
+                    Err(err) => load_failure(err, LoadPluginResult::FailedToLoadPaperPlugin),
+                }
+            }
+            Ok((None, Some(spigot))) => match self.load_spigot_plugin(plugin_path, &spigot) {
+                Ok(_) => LoadPluginResult::SuccessfullyLoadedSpigot,
+                Err(err) => load_failure(err, LoadPluginResult::FailedToLoadSpigotPlugin),
+            },
+            Ok((None, None)) => LoadPluginResult::NoConfigurationFile,
+            Err(err) => LoadPluginResult::FailedToReadConfigurationFile(err),
+        }
+    }
+
+    /// Disables a plugin, closes its class loader and forgets it.
+    ///
+    /// A plugin other loaded plugins depend on is not unloaded, as they would fail with
+    /// `NoClassDefFoundError` once its class loader is closed.
+    ///
+    /// Returns the unloaded plugin so it can be loaded again from the same jar.
+    pub async fn unload_plugin(
+        &mut self,
+        jvm: &Jvm,
+        context: &Arc<Context>,
         name: &str,
-        provides_map: &HashMap<String, String>,
-    ) -> Option<String> {
+    ) -> Result<Plugin> {
         let key = normalize_name(name);
-        if self.plugins.contains_key(&key) {
-            Some(key)
-        } else {
-            provides_map.get(&key).cloned()
+        if !self.plugins.contains_key(&key) {
+            return Err(anyhow!("No PatchBukkit plugin named {}", name));
+        }
+
+        let dependents = self.loaded_dependents(&key);
+        if !dependents.is_empty() {
+            return Err(anyhow!(
+                "{} cannot be unloaded while {} depend on it, unload them first",
+                self.plugins[&key].name,
+                self.plugin_names(&dependents).join(", ")
+            ));
         }
+
+        let plugin = self.unload(jvm, context, &key).await;
+        self.load_order.retain(|plugin_key| *plugin_key != key);
+        Ok(plugin)
     }
 
-    fn compute_load_order(&mut self) -> Vec<String> {
-        let mut provides_map: HashMap<String, String> = HashMap::new();
-        for (key, plugin) in &self.plugins {
-            for provide in &plugin.provides {
-                if provide.is_empty() {
+    /// Unloads a plugin, reads its jar again and bootstraps, instantiates and enables the
+    /// new copy.
+    ///
+    /// Plugins depending on it are unloaded first, in reverse load order, and loaded again
+    /// after it so they link against its new classes. Those that were enabled are enabled
+    /// again.
+    pub async fn reload_plugin(
+        &mut self,
+        jvm: &Jvm,
+        context: &Arc<Context>,
+        command_tx: mpsc::Sender<JvmCommand>,
+        command_manager: &mut CommandManager,
+        name: &str,
+    ) -> Result<()> {
+        let key = normalize_name(name);
+        if !self.plugins.contains_key(&key) {
+            return Err(anyhow!("No PatchBukkit plugin named {}", name));
+        }
+
+        let dependents = self.loaded_dependents(&key);
+        if !dependents.is_empty() {
+            log::info!(
+                "Reloading {} along with the plugins depending on it: {}",
+                self.plugins[&key].name,
+                self.plugin_names(&dependents).join(", ")
+            );
+        }
+
+        // Each plugin keeps its place in the load order while it is reloaded
+        let mut unloaded = Vec::new();
+        for plugin_key in dependents.iter().rev().chain(std::iter::once(&key)) {
+            let was_enabled = self.plugins[plugin_key].state == PluginState::Enabled;
+            let plugin = self.unload(jvm, context, plugin_key).await;
+            unloaded.push((plugin_key.clone(), plugin, was_enabled));
+        }
+
+        let plugin_manager = bukkit_plugin_manager(jvm)?;
+        let mut unloaded = unloaded.into_iter().rev();
+        while let Some((old_key, plugin, was_enabled)) = unloaded.next() {
+            let result = self
+                .load_again(
+                    jvm,
+                    context,
+                    &command_tx,
+                    command_manager,
+                    &old_key,
+                    &plugin,
+                )
+                .await;
+            let plugin_key = match result {
+                Ok(plugin_key) => plugin_key,
+                Err(e) if old_key == key => {
+                    // Without the plugin, its dependents cannot be loaded again
+                    let left_out: Vec<String> = unloaded.map(|(key, _, _)| key).collect();
+                    self.load_order.retain(|key| !left_out.contains(key));
+                    if !left_out.is_empty() {
+                        log::error!(
+                            "{} stay unloaded because {} failed to reload",
+                            left_out.join(", "),
+                            plugin.name
+                        );
+                    }
+                    return Err(e);
+                }
+                Err(e) => {
+                    log::error!("Failed to reload {}: {:?}", plugin.name, e);
                     continue;
                 }
-                provides_map.entry(provide.clone()).or_insert_with(|| key.clone());
+            };
+
+            let Some(plugin) = self.plugins.get_mut(&plugin_key) else {
+                continue;
+            };
+            if !was_enabled {
+                log::info!("Reloaded PatchBukkit plugin: {}", plugin.name);
+                continue;
+            }
+            enable_plugin(jvm, &plugin_manager, context, plugin).await;
+            match plugin.state {
+                PluginState::Enabled => log::info!("Reloaded PatchBukkit plugin: {}", plugin.name),
+                _ if plugin_key == key => {
+                    let root_cause = format!("{} failed to enable after reloading", plugin.name);
+                    self.cascade_errored(&plugin_key, &root_cause);
+                    return Err(anyhow!(root_cause));
+                }
+                _ => log::error!("{} failed to enable after reloading", plugin.name),
             }
         }
+        Ok(())
+    }
 
-        let mut active: HashSet<String> = self.plugins.keys().cloned().collect();
+    /// Disables a plugin, closes its class loader and removes it, leaving the load order as is
+    async fn unload(&mut self, jvm: &Jvm, context: &Arc<Context>, key: &str) -> Plugin {
+        let mut plugin = self.plugins.remove(key).expect("checked by the caller");
 
-        let mut missing_required_by_plugin: HashMap<String, Vec<String>> = HashMap::new();
-        for (key, plugin) in &self.plugins {
-            let mut missing_required = Vec::new();
-            for dep in &plugin.depends {
-                if self.resolve_dependency_name(dep, &provides_map).is_none() {
-                    missing_required.push(dep.clone());
-                }
+        match bukkit_plugin_manager(jvm) {
+            Ok(plugin_manager) => {
+                disable_plugin(jvm, &plugin_manager, context, &mut plugin).await;
             }
-            if !missing_required.is_empty() {
-                missing_required_by_plugin.insert(key.clone(), missing_required);
+            Err(e) => {
+                log::error!("Failed to disable {}: {:?}", plugin.name, e);
+                unregister_from_pumpkin(context, &mut plugin).await;
             }
         }
 
-        for (key, missing_required) in missing_required_by_plugin {
-            if let Some(plugin) = self.plugins.get_mut(&key) {
-                plugin.state = PluginState::Errored;
-                active.remove(&key);
-                log::warn!(
-                    "Skipping plugin {} due to missing required dependencies: {}",
+        if plugin.bootstrapped && plugin.instance.is_none() {
+            discard_bootstrap(jvm, &mut plugin);
+        }
+        if let Some(instance) = plugin.instance.take() {
+            // The plugin is already gone from PatchBukkit, a leaked class loader is only reported
+            if let Err(e) = jvm.invoke_static(
+                "org.patchbukkit.loader.PatchBukkitPluginLoader",
+                "unloadPlugin",
+                &[InvocationArg::from(instance)],
+            ) {
+                log::error!(
+                    "Failed to close the class loader of {}: {:?}",
                     plugin.name,
-                    missing_required.join(", ")
+                    e
                 );
             }
         }
 
-        let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
-        let mut indegree: HashMap<String, usize> =
-            active.iter().map(|k| (k.clone(), 0)).collect();
-
-        for (key, plugin) in &self.plugins {
-            if !active.contains(key) {
-                continue;
-            }
+        log::info!("Unloaded PatchBukkit plugin: {}", plugin.name);
+        plugin
+    }
 
-            let mut before = Vec::new();
-            for dep in plugin
-                .depends
+    /// Loads an unloaded plugin from its jar again, then bootstraps and instantiates it.
+    ///
+    /// Returns the key of the new copy, which takes the place of `old_key` in the load order.
+    async fn load_again(
+        &mut self,
+        jvm: &Jvm,
+        context: &Arc<Context>,
+        command_tx: &mpsc::Sender<JvmCommand>,
+        command_manager: &mut CommandManager,
+        old_key: &str,
+        unloaded: &Plugin,
+    ) -> Result<String> {
+        let loaded = match self.load_plugin(&unloaded.path) {
+            LoadPluginResult::SuccessfullyLoadedSpigot
+            | LoadPluginResult::SuccessfullyLoadedPaper => Ok(()),
+            LoadPluginResult::FailedToLoadSpigotPlugin(err)
+            | LoadPluginResult::FailedToLoadPaperPlugin(err)
+            | LoadPluginResult::FailedToReadConfigurationFile(err) => Err(err),
+            LoadPluginResult::IncompatibleApiVersion(err) => Err(err.into()),
+            LoadPluginResult::InvalidConfiguration(err) => Err(err.into()),
+            LoadPluginResult::DuplicatePlugin(err) => Err(err.into()),
+            LoadPluginResult::NoConfigurationFile => Err(anyhow!(
+                "{} no longer contains a plugin.yml or paper-plugin.yml",
+                unloaded.path.display()
+            )),
+        };
+        let plugin_key = loaded.and_then(|_| {
+            self.plugins
                 .iter()
-                .chain(plugin.soft_depends.iter())
-            {
-                if let Some(resolved) = self.resolve_dependency_name(dep, &provides_map) {
-                    if resolved != *key && active.contains(&resolved) {
-                        before.push(resolved);
-                    }
-                }
+                .find(|(_, plugin)| plugin.path == unloaded.path)
+                .map(|(key, _)| key.clone())
+                .ok_or_else(|| anyhow!("{} was not loaded again", unloaded.name))
+        });
+        let plugin_key = match plugin_key {
+            Ok(plugin_key) => plugin_key,
+            Err(e) => {
+                self.load_order.retain(|key| key != old_key);
+                return Err(e);
             }
+        };
 
-            for dep in before {
-                add_edge(&mut edges, &mut indegree, &dep, key);
+        if plugin_key != old_key {
+            match self.load_order.iter_mut().find(|key| *key == old_key) {
+                Some(key) => *key = plugin_key.clone(),
+                None => self.load_order.push(plugin_key.clone()),
             }
+        }
 
-            for target in &plugin.load_before {
-                if let Some(resolved) = self.resolve_dependency_name(target, &provides_map) {
-                    if resolved != *key && active.contains(&resolved) {
-                        add_edge(&mut edges, &mut indegree, key, &resolved);
-                    }
+        self.bootstrap_plugin(jvm, &plugin_key)?;
+        self.instantiate_plugin(jvm, context, command_tx, command_manager, &plugin_key)
+            .await?;
+        match self.plugins.get(&plugin_key) {
+            Some(plugin) if plugin.instance.is_some() => Ok(plugin_key),
+            _ => Err(anyhow!("{} could not be instantiated", unloaded.name)),
+        }
+    }
+
+    /// Instantiated plugins depending on `key` through `depend`, `softdepend` or a joined
+    /// classpath, directly or not, in load order
+    fn loaded_dependents(&self, key: &str) -> Vec<String> {
+        let provides_map = self.provides_map();
+        let mut dependents = HashSet::new();
+        let mut queue = VecDeque::from([key.to_string()]);
+        while let Some(dependency) = queue.pop_front() {
+            for (plugin_key, plugin) in &self.plugins {
+                if plugin.instance.is_none()
+                    || *plugin_key == key
+                    || dependents.contains(plugin_key)
+                {
+                    continue;
+                }
+                let depends_on_it = plugin
+                    .depends
+                    .iter()
+                    .chain(&plugin.soft_depends)
+                    .chain(&plugin.classpath_deps)
+                    .any(|dep| {
+                        self.resolve_dependency_name(dep, &provides_map).as_ref()
+                            == Some(&dependency)
+                    });
+                if depends_on_it {
+                    dependents.insert(plugin_key.clone());
+                    queue.push_back(plugin_key.clone());
                 }
             }
+        }
+        self.load_order
+            .iter()
+            .filter(|plugin_key| dependents.contains(*plugin_key))
+            .cloned()
+            .collect()
+    }
 
-            for target in &plugin.load_after {
-                if let Some(resolved) = self.resolve_dependency_name(target, &provides_map) {
-                    if resolved != *key && active.contains(&resolved) {
-                        add_edge(&mut edges, &mut indegree, &resolved, key);
-                    }
+    /// Warns about STARTUP plugins depending on POSTWORLD plugins, which are not enabled yet
+    /// when the STARTUP plugins are
+    fn warn_postworld_dependencies(&self) {
+        let provides_map = self.provides_map();
+        for plugin_key in &self.load_order {
+            let Some(plugin) = self.plugins.get(plugin_key) else {
+                continue;
+            };
+            if plugin.load != LoadOrder::Startup || plugin.state == PluginState::Errored {
+                continue;
+            }
+            for dep in plugin.depends.iter().chain(&plugin.soft_depends) {
+                let Some(dependency) = self
+                    .resolve_dependency_name(dep, &provides_map)
+                    .and_then(|dep_key| self.plugins.get(&dep_key))
+                else {
+                    continue;
+                };
+                if dependency.load == LoadOrder::Postworld {
+                    log::warn!(
+                        "{} loads at STARTUP but depends on {}, which loads at POSTWORLD and is not enabled yet when {} is",
+                        plugin.name,
+                        dependency.name,
+                        plugin.name
+                    );
+                }
+            }
+        }
+    }
+
+    fn plugin_names(&self, keys: &[String]) -> Vec<String> {
+        keys.iter()
+            .map(|key| {
+                self.plugins
+                    .get(key)
+                    .map_or(key, |plugin| &plugin.name)
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Runs the loader and bootstrapper of every Paper plugin that declares one.
+    ///
+    /// Plugins are bootstrapped in the order given by their `dependencies.bootstrap`
+    /// section, before any plugin class is instantiated.
+    pub fn bootstrap_all_plugins(&mut self, jvm: &Jvm) -> Result<()> {
+        for plugin_key in self.compute_bootstrap_order() {
+            self.bootstrap_plugin(jvm, &plugin_key)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the loader and bootstrapper of one Paper plugin, if it declares one
+    fn bootstrap_plugin(&mut self, jvm: &Jvm, plugin_key: &str) -> Result<()> {
+        let (classpath, libraries) = match self.plugins.get(plugin_key) {
+            Some(plugin) => (
+                self.classpath_string_for(plugin),
+                self.library_string_for(plugin),
+            ),
+            None => return Ok(()),
+        };
+
+        let plugin = match self.plugins.get_mut(plugin_key) {
+            Some(plugin) => plugin,
+            None => return Ok(()),
+        };
+        let PluginType::Paper(data) = &plugin.plugin_type else {
+            return Ok(());
+        };
+        let config = &data.paper_config;
+        if plugin.bootstrapped
+            || plugin.instance.is_some()
+            || plugin.state == PluginState::Errored
+            || !(config.has_bootstrapper() || config.has_loader())
+        {
+            return Ok(());
+        }
+        watchdog::running_plugin(&plugin.name);
+        let bootstrapper = config.bootstrapper.clone().unwrap_or_default();
+        let loader = config.loader.clone().unwrap_or_default();
+
+        let result = create_data_folder(plugin).and_then(|_| {
+            Ok(jvm.invoke_static(
+                "org.patchbukkit.loader.PatchBukkitPluginLoader",
+                "bootstrapPlugin",
+                &[
+                    InvocationArg::try_from(&plugin.path.to_string_lossy().to_string())?,
+                    InvocationArg::try_from(&bootstrapper)?,
+                    InvocationArg::try_from(&loader)?,
+                    InvocationArg::try_from(&plugin.data_folder.to_string_lossy().to_string())?,
+                    InvocationArg::try_from(&classpath)?,
+                    InvocationArg::try_from(&libraries)?,
+                ],
+            )?)
+        });
+
+        match result {
+            Ok(_) => {
+                plugin.bootstrapped = true;
+                log::info!("Bootstrapped: {}", plugin.name);
+            }
+            Err(e) => {
+                log::error!("Failed to bootstrap {}: {:?}", plugin.name, e);
+                plugin.state = PluginState::Errored;
+                let root_cause = format!("{} failed to bootstrap", plugin.name);
+                self.cascade_errored(plugin_key, &root_cause);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn instantiate_all_plugins(
+        &mut self,
+        jvm: &Jvm,
+        server: &Arc<Context>,
+        command_tx: mpsc::Sender<JvmCommand>,
+        command_manager: &mut CommandManager,
+    ) -> Result<()> {
+        self.migrate_legacy_data_folders();
+
+        self.load_order = self.compute_load_order();
+
+        for plugin_key in self.load_order.clone() {
+            self.instantiate_plugin(jvm, server, &command_tx, command_manager, &plugin_key)
+                .await?;
+        }
+
+        // Plugins errored after their bootstrap phase, e.g. by a failed dependency
+        for plugin in self.plugins.values_mut() {
+            if plugin.bootstrapped && plugin.instance.is_none() {
+                discard_bootstrap(jvm, plugin);
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the instance of one plugin and registers it with Pumpkin.
+    ///
+    /// A plugin that cannot be instantiated is marked errored, along with its dependents.
+    async fn instantiate_plugin(
+        &mut self,
+        jvm: &Jvm,
+        server: &Arc<Context>,
+        command_tx: &mpsc::Sender<JvmCommand>,
+        command_manager: &mut CommandManager,
+        plugin_key: &str,
+    ) -> Result<()> {
+        let (classpath, libraries) = match self.plugins.get(plugin_key) {
+            Some(plugin) => (
+                self.classpath_string_for(plugin),
+                self.library_string_for(plugin),
+            ),
+            None => return Ok(()),
+        };
+
+        let plugin = match self.plugins.get_mut(plugin_key) {
+            Some(plugin) if plugin.instance.is_none() && plugin.state != PluginState::Errored => {
+                plugin
+            }
+            _ => return Ok(()),
+        };
+        watchdog::running_plugin(&plugin.name);
+        let plugin_instance = match create_data_folder(plugin)
+            .and_then(|_| create_plugin(jvm, plugin, &classpath, &libraries))
+        {
+            Ok(plugin_instance) => plugin_instance,
+            Err(e) => {
+                plugin.state = PluginState::Errored;
+                log::error!(
+                    "Failed to instantiate PatchBukkit plugin {}: {:?}",
+                    plugin.name,
+                    e
+                );
+                let root_cause = format!("{} failed to instantiate", plugin.name);
+                self.cascade_errored(plugin_key, &root_cause);
+                return Ok(());
+            }
+        };
+
+        plugin.instance = Some(plugin_instance);
+        register_with_pumpkin(jvm, server, command_tx.clone(), command_manager, plugin).await;
+
+        plugin.state = PluginState::Loaded;
+        log::info!("Loaded and registered commands for: {}", plugin.name);
+        Ok(())
+    }
+
+    pub fn unload_all_plugins(&mut self) -> Result<()> {
+        self.plugins.clear();
+        self.skipped.clear();
+        Ok(())
+    }
+
+    /// Moves files out of the old shared `data/` folder into each plugin's own data folder.
+    ///
+    /// Files inside `data/<PluginName>/` go to that plugin. Loose files can only be
+    /// attributed when a single plugin lives next to the legacy folder, otherwise they
+    /// are left in place and reported.
+    fn migrate_legacy_data_folders(&self) {
+        let mut plugins_by_folder: HashMap<PathBuf, Vec<&Plugin>> = HashMap::new();
+        for plugin in self.plugins.values() {
+            if let Some(parent) = plugin.data_folder.parent() {
+                plugins_by_folder
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(plugin);
+            }
+        }
+
+        for (plugins_folder, plugins) in plugins_by_folder {
+            let legacy_folder = plugins_folder.join(LEGACY_DATA_FOLDER);
+            if !legacy_folder.is_dir() {
+                continue;
+            }
+            if plugins
+                .iter()
+                .any(|plugin| normalize_name(&plugin.name) == LEGACY_DATA_FOLDER)
+            {
+                // A plugin actually called `data` owns this folder now
+                continue;
+            }
+
+            for plugin in &plugins {
+                let plugin_legacy_folder = legacy_folder.join(&plugin.name);
+                if plugin_legacy_folder.is_dir() {
+                    move_folder_contents(&plugin_legacy_folder, &plugin.data_folder, &plugin.name);
+                    let _ = fs::remove_dir(&plugin_legacy_folder);
+                }
+            }
+
+            let leftovers: Vec<PathBuf> = match fs::read_dir(&legacy_folder) {
+                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+                Err(e) => {
+                    log::error!(
+                        "Failed to read legacy data folder {}: {}",
+                        legacy_folder.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            if !leftovers.is_empty() {
+                if let [plugin] = plugins.as_slice() {
+                    move_folder_contents(&legacy_folder, &plugin.data_folder, &plugin.name);
+                } else {
+                    log::warn!(
+                        "Legacy data folder {} still contains files that cannot be attributed to a single plugin, move them into the matching plugin folder manually: {}",
+                        legacy_folder.display(),
+                        leftovers
+                            .iter()
+                            .filter_map(|p| p.file_name())
+                            .map(|n| n.to_string_lossy().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+
+            if fs::remove_dir(&legacy_folder).is_ok() {
+                log::info!(
+                    "Removed empty legacy data folder {}",
+                    legacy_folder.display()
+                );
+            }
+        }
+    }
+
+    /// Maps every `provides` alias to the key of the first plugin providing it
+    fn provides_map(&self) -> HashMap<String, String> {
+        let mut provides_map: HashMap<String, String> = HashMap::new();
+        for (key, plugin) in &self.plugins {
+            for provide in &plugin.provides {
+                if provide.is_empty() {
+                    continue;
+                }
+                provides_map.entry(provide.clone()).or_insert_with(|| key.clone());
+            }
+        }
+        provides_map
+    }
+
+    /// Marks every plugin that transitively `depend`s on an errored plugin as errored too.
+    ///
+    /// Returns the keys of the plugins that were marked.
+    fn cascade_errored(&mut self, root: &str, root_cause: &str) -> Vec<String> {
+        let provides_map = self.provides_map();
+        let mut cascaded = Vec::new();
+        let mut queue = VecDeque::from([root.to_string()]);
+        while let Some(failed) = queue.pop_front() {
+            let mut dependents: Vec<String> = self
+                .plugins
+                .iter()
+                .filter(|(key, plugin)| {
+                    **key != failed
+                        && plugin.state != PluginState::Errored
+                        && plugin.depends.iter().any(|dep| {
+                            self.resolve_dependency_name(dep, &provides_map).as_ref()
+                                == Some(&failed)
+                        })
+                })
+                .map(|(key, _)| key.clone())
+                .collect();
+            dependents.sort();
+
+            let failed_name = self
+                .plugins
+                .get(&failed)
+                .map_or_else(|| failed.clone(), |plugin| plugin.name.clone());
+            for key in dependents {
+                if let Some(plugin) = self.plugins.get_mut(&key) {
+                    plugin.state = PluginState::Errored;
+                    log::error!(
+                        "Not enabling {} because its dependency {} is errored (root cause: {})",
+                        plugin.name,
+                        failed_name,
+                        root_cause
+                    );
+                }
+                queue.push_back(key.clone());
+                cascaded.push(key);
+            }
+        }
+        cascaded
+    }
+
+    fn resolve_dependency_name(
+        &self,
+        name: &str,
+        provides_map: &HashMap<String, String>,
+    ) -> Option<String> {
+        let key = normalize_name(name);
+        if self.plugins.contains_key(&key) {
+            Some(key)
+        } else {
+            provides_map.get(&key).cloned()
+        }
+    }
+
+    /// The dependency problems found when the plugins were last instantiated
+    pub fn dependency_report(&self) -> &DependencyReport {
+        &self.dependency_report
+    }
+
+    fn compute_load_order(&mut self) -> Vec<String> {
+        let provides_map = self.provides_map();
+        let mut report = DependencyReport {
+            provides_collisions: self.provides_collisions(&provides_map),
+            ..Default::default()
+        };
+
+        let mut active: HashSet<String> = self.plugins.keys().cloned().collect();
+
+        let mut missing_required_by_plugin: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, plugin) in &self.plugins {
+            let mut missing_required = Vec::new();
+            for dep in &plugin.depends {
+                match self.resolve_dependency_name(dep, &provides_map) {
+                    None => missing_required.push(dep.clone()),
+                    Some(resolved) => {
+                        if let Some(mismatch) = self.version_mismatch(plugin, dep, &resolved) {
+                            missing_required.push(format!(
+                                "{} {} (found {})",
+                                dep, mismatch.required, mismatch.found
+                            ));
+                            report.version_mismatches.push(mismatch);
+                        }
+                    }
+                }
+            }
+            if !missing_required.is_empty() {
+                missing_required_by_plugin.insert(key.clone(), missing_required);
+            }
+        }
+
+        let mut missing_required_by_plugin: Vec<(String, Vec<String>)> =
+            missing_required_by_plugin.into_iter().collect();
+        missing_required_by_plugin.sort();
+        for (key, missing_required) in missing_required_by_plugin {
+            if let Some(plugin) = self.plugins.get_mut(&key) {
+                plugin.state = PluginState::Errored;
+                active.remove(&key);
+                log::warn!(
+                    "Skipping plugin {} due to missing required dependencies: {}",
+                    plugin.name,
+                    missing_required.join(", ")
+                );
+                let root_cause =
+                    format!("{} is missing {}", plugin.name, missing_required.join(", "));
+                report.missing_dependencies.push(MissingDependencies {
+                    plugin: plugin.name.clone(),
+                    missing: missing_required,
+                });
+                for cascaded in self.cascade_errored(&key, &root_cause) {
+                    active.remove(&cascaded);
+                }
+            }
+        }
+
+        for (key, plugin) in &self.plugins {
+            if !active.contains(key) {
+                continue;
+            }
+            for dep in &plugin.soft_depends {
+                let reason = match self.resolve_dependency_name(dep, &provides_map) {
+                    None => SkipReason::NotInstalled,
+                    Some(resolved) if !active.contains(&resolved) => SkipReason::Errored,
+                    Some(resolved) => match self.version_mismatch(plugin, dep, &resolved) {
+                        Some(mismatch) => {
+                            report.version_mismatches.push(mismatch);
+                            SkipReason::VersionMismatch
+                        }
+                        None => continue,
+                    },
+                };
+                report
+                    .skipped_soft_dependencies
+                    .push(SkippedSoftDependency {
+                        plugin: plugin.name.clone(),
+                        dependency: dep.clone(),
+                        reason,
+                    });
+            }
+        }
+        report
+            .skipped_soft_dependencies
+            .sort_by(|a, b| (&a.plugin, &a.dependency).cmp(&(&b.plugin, &b.dependency)));
+
+        let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut indegree: HashMap<String, usize> =
+            active.iter().map(|k| (k.clone(), 0)).collect();
+
+        for (key, plugin) in &self.plugins {
+            if !active.contains(key) {
+                continue;
+            }
+
+            let mut before = Vec::new();
+            for dep in plugin
+                .depends
+                .iter()
+                .chain(plugin.soft_depends.iter())
+            {
+                if let Some(resolved) = self.resolve_dependency_name(dep, &provides_map) {
+                    if resolved != *key && active.contains(&resolved) {
+                        before.push(resolved);
+                    }
+                }
+            }
+
+            for dep in before {
+                add_edge(&mut edges, &mut indegree, &dep, key);
+            }
+
+            for target in &plugin.load_before {
+                if let Some(resolved) = self.resolve_dependency_name(target, &provides_map) {
+                    if resolved != *key && active.contains(&resolved) {
+                        add_edge(&mut edges, &mut indegree, key, &resolved);
+                    }
+                }
+            }
+
+            for target in &plugin.load_after {
+                if let Some(resolved) = self.resolve_dependency_name(target, &provides_map) {
+                    if resolved != *key && active.contains(&resolved) {
+                        add_edge(&mut edges, &mut indegree, &resolved, key);
+                    }
                 }
             }
         }
@@ -546,31 +1454,197 @@ impl PluginManager {
                 .filter(|k| !order.contains(k))
                 .collect();
             remaining.sort();
+            report.cycles = find_cycles(&remaining, &edges);
+            for cycle in &report.cycles {
+                log::warn!(
+                    "Detected plugin dependency cycle between: {}",
+                    cycle.join(", ")
+                );
+            }
             log::warn!(
-                "Detected plugin dependency cycle(s). Loading remaining plugins in name order: {}",
+                "Loading plugins stuck behind dependency cycles in name order: {}",
                 remaining.join(", ")
             );
             order.extend(remaining);
         }
 
+        self.dependency_report = report;
         order
     }
 
-    fn classpath_string_for(&self, plugin: &Plugin) -> String {
-        if plugin.classpath_deps.is_empty() {
-            return String::new();
+    /// Checks the installed version of a dependency against the range `plugin` declares for it
+    fn version_mismatch(
+        &self,
+        plugin: &Plugin,
+        dependency: &str,
+        resolved: &str,
+    ) -> Option<VersionMismatch> {
+        let range = plugin.dependency_versions.get(dependency)?;
+        let installed = self.plugins.get(resolved)?;
+        let matches = Version::parse(&installed.version)
+            .map(|version| range.matches(&version))
+            .unwrap_or(false);
+        if matches {
+            return None;
         }
+        Some(VersionMismatch {
+            plugin: plugin.name.clone(),
+            dependency: installed.name.clone(),
+            required: range.to_string(),
+            found: installed.version.clone(),
+        })
+    }
 
-        let mut provides_map: HashMap<String, String> = HashMap::new();
+    /// Names claimed by more than one plugin, through its own name or `provides`
+    fn provides_collisions(
+        &self,
+        provides_map: &HashMap<String, String>,
+    ) -> Vec<ProvidesCollision> {
+        let mut claims: HashMap<&str, Vec<String>> = HashMap::new();
         for (key, plugin) in &self.plugins {
-            for provide in &plugin.provides {
-                if provide.is_empty() {
+            claims
+                .entry(key.as_str())
+                .or_default()
+                .push(plugin.name.clone());
+            for provide in plugin.provides.iter().filter(|provide| !provide.is_empty()) {
+                if provide != key {
+                    claims
+                        .entry(provide.as_str())
+                        .or_default()
+                        .push(plugin.name.clone());
+                }
+            }
+        }
+
+        let mut collisions: Vec<ProvidesCollision> = claims
+            .into_iter()
+            .filter(|(_, providers)| providers.len() > 1)
+            .filter_map(|(name, mut providers)| {
+                let resolved = self.resolve_dependency_name(name, provides_map)?;
+                providers.sort();
+                Some(ProvidesCollision {
+                    name: name.to_string(),
+                    providers,
+                    resolved_to: self.plugins.get(&resolved)?.name.clone(),
+                })
+            })
+            .collect();
+        collisions.sort_by(|a, b| a.name.cmp(&b.name));
+        collisions
+    }
+
+    /// Orders the Paper plugins awaiting their bootstrap phase.
+    ///
+    /// `load: BEFORE` bootstraps the dependency first, `load: AFTER` bootstraps it
+    /// afterwards and `OMIT` leaves the pair unordered. Plugins missing a required
+    /// bootstrap dependency are left out.
+    fn compute_bootstrap_order(&self) -> Vec<String> {
+        let provides_map = self.provides_map();
+
+        let mut pending: HashMap<String, Vec<(String, &PaperDependency)>> = HashMap::new();
+        for (key, plugin) in &self.plugins {
+            let PluginType::Paper(data) = &plugin.plugin_type else {
+                continue;
+            };
+            let config = &data.paper_config;
+            if plugin.bootstrapped
+                || plugin.instance.is_some()
+                || plugin.state == PluginState::Errored
+                || !(config.has_bootstrapper() || config.has_loader())
+            {
+                continue;
+            }
+
+            let bootstrap_deps = config
+                .dependencies
+                .as_ref()
+                .map(|deps| deps.get_bootstrap_deps())
+                .unwrap_or_default();
+            let mut resolved_deps = Vec::new();
+            let mut missing_required = Vec::new();
+            for (name, dep) in bootstrap_deps {
+                match self.resolve_dependency_name(name, &provides_map) {
+                    Some(resolved) => resolved_deps.push((resolved, dep)),
+                    None if dep.required => missing_required.push(name.clone()),
+                    None => {}
+                }
+            }
+
+            if missing_required.is_empty() {
+                pending.insert(key.clone(), resolved_deps);
+            } else {
+                missing_required.sort();
+                log::warn!(
+                    "Not bootstrapping {} due to missing required bootstrap dependencies: {}",
+                    plugin.name,
+                    missing_required.join(", ")
+                );
+            }
+        }
+
+        let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut indegree: HashMap<String, usize> = pending.keys().map(|k| (k.clone(), 0)).collect();
+        for (key, deps) in &pending {
+            for (dep, declaration) in deps {
+                if !pending.contains_key(dep) {
                     continue;
                 }
-                provides_map.entry(provide.clone()).or_insert_with(|| key.clone());
+                match declaration.load {
+                    PaperLoadOrder::Before => add_edge(&mut edges, &mut indegree, dep, key),
+                    PaperLoadOrder::After => add_edge(&mut edges, &mut indegree, key, dep),
+                    PaperLoadOrder::Omit => {}
+                }
+            }
+        }
+
+        let mut ready: BinaryHeap<std::cmp::Reverse<String>> = indegree
+            .iter()
+            .filter(|(_, deg)| **deg == 0)
+            .map(|(key, _)| std::cmp::Reverse(key.clone()))
+            .collect();
+        let mut order = Vec::with_capacity(pending.len());
+
+        while let Some(std::cmp::Reverse(node)) = ready.pop() {
+            order.push(node.clone());
+            if let Some(children) = edges.get(&node) {
+                for child in children {
+                    if let Some(deg) = indegree.get_mut(child) {
+                        *deg = deg.saturating_sub(1);
+                        if *deg == 0 {
+                            ready.push(std::cmp::Reverse(child.clone()));
+                        }
+                    }
+                }
             }
         }
 
+        if order.len() != pending.len() {
+            let mut remaining: Vec<String> =
+                pending.into_keys().filter(|k| !order.contains(k)).collect();
+            remaining.sort();
+            for cycle in find_cycles(&remaining, &edges) {
+                log::warn!(
+                    "Detected bootstrap dependency cycle between: {}",
+                    cycle.join(", ")
+                );
+            }
+            log::warn!(
+                "Bootstrapping plugins stuck behind bootstrap dependency cycles in name order: {}",
+                remaining.join(", ")
+            );
+            order.extend(remaining);
+        }
+
+        order
+    }
+
+    fn classpath_string_for(&self, plugin: &Plugin) -> String {
+        if plugin.classpath_deps.is_empty() {
+            return String::new();
+        }
+
+        let provides_map = self.provides_map();
+
         let mut paths = Vec::new();
         for dep in &plugin.classpath_deps {
             if let Some(resolved) = self.resolve_dependency_name(dep, &provides_map) {
@@ -591,6 +1665,316 @@ impl PluginManager {
     }
 }
 
+/// Reports invalid descriptions and incompatible `api-version`s as such, any other error
+/// through `failed`
+fn load_failure(
+    err: anyhow::Error,
+    failed: fn(anyhow::Error) -> LoadPluginResult,
+) -> LoadPluginResult {
+    let err = match err.downcast::<PluginConfigError>() {
+        Ok(invalid) => return LoadPluginResult::InvalidConfiguration(invalid),
+        Err(err) => err,
+    };
+    let err = match err.downcast::<DuplicatePlugin>() {
+        Ok(duplicate) => return LoadPluginResult::DuplicatePlugin(duplicate),
+        Err(err) => err,
+    };
+    match err.downcast::<IncompatibleApiVersion>() {
+        Ok(incompatible) => LoadPluginResult::IncompatibleApiVersion(incompatible),
+        Err(err) => failed(err),
+    }
+}
+
+fn bukkit_plugin_manager(jvm: &Jvm) -> Result<Instance> {
+    Ok(jvm.invoke_static(
+        "org.bukkit.Bukkit",
+        "getPluginManager",
+        InvocationArg::empty(),
+    )?)
+}
+
+/// Creates the instance of a plugin's main class, a plugin whose class cannot be loaded or
+/// constructed is an error
+fn create_plugin(jvm: &Jvm, plugin: &Plugin, classpath: &str, libraries: &str) -> Result<Instance> {
+    let plugin_instance = jvm.invoke_static(
+        "org.patchbukkit.loader.PatchBukkitPluginLoader",
+        "createPlugin",
+        &[
+            InvocationArg::try_from(&plugin.path.to_string_lossy().to_string())?,
+            InvocationArg::try_from(&plugin.main_class)?,
+            InvocationArg::try_from(&plugin.data_folder.to_string_lossy().to_string())?,
+            InvocationArg::try_from(classpath)?,
+            InvocationArg::try_from(libraries)?,
+        ],
+    )?;
+
+    let is_null = jvm.invoke_static(
+        "java.util.Objects",
+        "isNull",
+        &[InvocationArg::from(jvm.clone_instance(&plugin_instance)?)],
+    )?;
+    if jvm.to_rust::<bool>(is_null)? {
+        return Err(anyhow!(
+            "createPlugin returned no instance of {}",
+            plugin.main_class
+        ));
+    }
+    Ok(plugin_instance)
+}
+
+/// Enables a plugin through the Bukkit plugin manager.
+///
+/// The plugin manager logs an exception thrown by `onEnable` and disables the plugin again,
+/// as Paper does, so the plugin is only `Enabled` if it still reports being enabled
+/// afterwards. A plugin that failed is `Errored` and removed from Pumpkin.
+async fn enable_plugin(
+    jvm: &Jvm,
+    plugin_manager: &Instance,
+    context: &Arc<Context>,
+    plugin: &mut Plugin,
+) {
+    let Some(plugin_instance) = &plugin.instance else {
+        return;
+    };
+    watchdog::running_plugin(&plugin.name);
+
+    let result = jvm
+        .clone_instance(plugin_instance)
+        .and_then(|plugin_instance| {
+            jvm.invoke(
+                plugin_manager,
+                "enablePlugin",
+                &[InvocationArg::from(plugin_instance)],
+            )
+        })
+        .and_then(|_| jvm.invoke(plugin_instance, "isEnabled", InvocationArg::empty()))
+        .and_then(|enabled| jvm.to_rust::<bool>(enabled));
+
+    // Listeners registered from onEnable, kept even on failure so they can be torn down
+    claim_listeners(plugin);
+
+    match result {
+        Ok(true) => {
+            plugin.state = PluginState::Enabled;
+            log::info!("Enabled PatchBukkit plugin: {}", plugin.name);
+        }
+        Ok(false) => {
+            plugin.state = PluginState::Errored;
+            log::error!(
+                "PatchBukkit plugin {} failed to enable and was disabled again",
+                plugin.name
+            );
+            unregister_from_pumpkin(context, plugin).await;
+        }
+        Err(e) => {
+            plugin.state = PluginState::Errored;
+            log::error!(
+                "Failed to enable PatchBukkit plugin {}: {:?}",
+                plugin.name,
+                e
+            );
+            unregister_from_pumpkin(context, plugin).await;
+        }
+    }
+}
+
+/// Closes the class loader kept for a bootstrapped plugin that will not be instantiated
+fn discard_bootstrap(jvm: &Jvm, plugin: &mut Plugin) {
+    plugin.bootstrapped = false;
+    let jar_path = plugin.path.to_string_lossy().to_string();
+    let result = InvocationArg::try_from(&jar_path).and_then(|jar_path| {
+        jvm.invoke_static(
+            "org.patchbukkit.loader.PatchBukkitPluginLoader",
+            "discardBootstrap",
+            &[jar_path],
+        )
+    });
+    if let Err(e) = result {
+        log::error!(
+            "Failed to close the class loader of {}: {:?}",
+            plugin.name,
+            e
+        );
+    }
+}
+
+async fn disable_plugin(
+    jvm: &Jvm,
+    plugin_manager: &Instance,
+    context: &Arc<Context>,
+    plugin: &mut Plugin,
+) {
+    let Some(plugin_instance) = &plugin.instance else {
+        return;
+    };
+    watchdog::running_plugin(&plugin.name);
+
+    let result = jvm
+        .clone_instance(plugin_instance)
+        .and_then(|plugin_instance| {
+            jvm.invoke(
+                plugin_manager,
+                "disablePlugin",
+                &[InvocationArg::from(plugin_instance)],
+            )
+        });
+
+    match result {
+        Ok(_) => {
+            plugin.state = PluginState::Disabled;
+            log::info!("Disabled PatchBukkit plugin: {}", plugin.name);
+        }
+        Err(e) => {
+            plugin.state = PluginState::Disabled;
+            log::error!(
+                "Failed to disable PatchBukkit plugin {}: {:?}",
+                plugin.name,
+                e
+            );
+        }
+    }
+
+    unregister_from_pumpkin(context, plugin).await;
+}
+
+/// Registers a plugin's permissions and commands with Pumpkin
+async fn register_with_pumpkin(
+    jvm: &Jvm,
+    context: &Arc<Context>,
+    command_tx: mpsc::Sender<JvmCommand>,
+    command_manager: &mut CommandManager,
+    plugin: &mut Plugin,
+) {
+    let mut registered_permissions = register_plugin_permissions(context, plugin).await;
+    let mut registered_commands = Vec::new();
+    for (cmd_name, cmd_data) in &plugin.commands {
+        match command_manager
+            .register_command(
+                jvm,
+                context,
+                plugin,
+                cmd_name.clone(),
+                cmd_data,
+                command_tx.clone(),
+            )
+            .await
+        {
+            Ok((labels, permissions)) => {
+                registered_commands.extend(labels);
+                registered_permissions.extend(permissions);
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to register command {} for plugin {}: {:?}",
+                    cmd_name,
+                    plugin.name,
+                    e
+                );
+            }
+        }
+    }
+    plugin.registered_commands = registered_commands;
+    plugin.registered_permissions = registered_permissions;
+}
+
+/// Moves listeners Java registered for the plugin since the last claim onto the plugin
+fn claim_listeners(plugin: &mut Plugin) {
+    plugin
+        .listeners
+        .extend(take_registered_listeners(&plugin.name));
+}
+
+/// Tears down everything a plugin registered with Pumpkin, so nothing forwards to it anymore
+async fn unregister_from_pumpkin(context: &Arc<Context>, plugin: &mut Plugin) {
+    claim_listeners(plugin);
+    for listener in plugin.listeners.drain(..) {
+        listener.unregister();
+    }
+    for command in plugin.registered_commands.drain(..) {
+        context.unregister_command(&command).await;
+    }
+    for node in plugin.registered_permissions.drain(..) {
+        unregister_permission(context, &node).await;
+    }
+    log::debug!(
+        "Unregistered commands, permissions and listeners of {}",
+        plugin.name
+    );
+}
+
+const LEGACY_DATA_FOLDER: &str = "data";
+
+/// The `<plugins>/<PluginName>/` data folder next to the plugin jar.
+///
+/// It is only created once the plugin is bootstrapped or instantiated, so a jar losing to a
+/// duplicate leaves nothing behind.
+fn data_folder_for(jar_path: &Path, plugin_name: &str) -> Result<PathBuf> {
+    let plugins_folder = jar_path
+        .parent()
+        .ok_or_else(|| anyhow!("Plugin jar {} has no parent folder", jar_path.display()))?;
+    Ok(plugins_folder.join(plugin_name))
+}
+
+fn create_data_folder(plugin: &Plugin) -> Result<()> {
+    fs::create_dir_all(&plugin.data_folder).map_err(|e| {
+        anyhow!(
+            "Failed to create the data folder {}: {}",
+            plugin.data_folder.display(),
+            e
+        )
+    })
+}
+
+fn move_folder_contents(from: &Path, to: &Path, plugin_name: &str) {
+    if let Err(e) = fs::create_dir_all(to) {
+        log::error!(
+            "Not migrating {} for plugin {}: {}",
+            from.display(),
+            plugin_name,
+            e
+        );
+        return;
+    }
+    let entries = match fs::read_dir(from) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!(
+                "Failed to read legacy data folder {}: {}",
+                from.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let target = to.join(entry.file_name());
+        if target.exists() {
+            log::warn!(
+                "Not migrating {} for plugin {}: {} already exists",
+                entry.path().display(),
+                plugin_name,
+                target.display()
+            );
+            continue;
+        }
+        match fs::rename(entry.path(), &target) {
+            Ok(_) => log::info!(
+                "Migrated {} to {} for plugin {}",
+                entry.path().display(),
+                target.display(),
+                plugin_name
+            ),
+            Err(e) => log::error!(
+                "Failed to migrate {} for plugin {}: {}",
+                entry.path().display(),
+                plugin_name,
+                e
+            ),
+        }
+    }
+}
+
 fn normalize_name(name: &str) -> String {
     name.trim().to_lowercase()
 }
@@ -646,3 +2030,178 @@ fn add_edge(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_plugin(name: &str, version: &str, path: &str) -> Plugin {
+        let yaml = format!("name: {}\nversion: {}\nmain: a.B\n", name, version);
+        Plugin {
+            name: name.to_string(),
+            version: version.to_string(),
+            main_class: "a.B".to_string(),
+            load: LoadOrder::Postworld,
+            path: PathBuf::from(path),
+            plugin_type: PluginType::Spigot(SpigotPluginData {
+                spigot_config: SpigotPluginYml::from_str(&yaml).unwrap(),
+            }),
+            state: PluginState::Registered,
+            data_folder: PathBuf::new(),
+            instance: None,
+            bootstrapped: false,
+            commands: HashMap::new(),
+            listeners: Vec::new(),
+            registered_commands: Vec::new(),
+            registered_permissions: Vec::new(),
+            provides: Vec::new(),
+            depends: Vec::new(),
+            soft_depends: Vec::new(),
+            load_before: Vec::new(),
+            load_after: Vec::new(),
+            classpath_deps: Vec::new(),
+            dependency_versions: HashMap::new(),
+            libraries: Vec::new(),
+        }
+    }
+
+    fn depending_on(name: &str, depends: &[&str]) -> Plugin {
+        let mut plugin = test_plugin(name, "1.0", &format!("{}.jar", name));
+        plugin.depends = depends.iter().map(|dep| dep.to_string()).collect();
+        plugin
+    }
+
+    fn manager_with(plugins: Vec<Plugin>) -> PluginManager {
+        let mut manager = PluginManager::new();
+        for plugin in plugins {
+            manager.add_plugin(plugin).unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn cascade_errored_follows_required_dependencies() {
+        let mut core = depending_on("Core", &[]);
+        core.provides = vec!["coreapi".to_string()];
+        let mut soft = depending_on("Soft", &[]);
+        soft.soft_depends = vec!["core".to_string()];
+        let mut errored = depending_on("Errored", &["core"]);
+        errored.state = PluginState::Errored;
+        let mut manager = manager_with(vec![
+            core,
+            depending_on("A", &["core"]),
+            depending_on("B", &["coreapi"]),
+            depending_on("C", &["a"]),
+            soft,
+            errored,
+        ]);
+        manager.plugins.get_mut("core").unwrap().state = PluginState::Errored;
+
+        let cascaded = manager.cascade_errored("core", "Core failed to enable");
+        assert_eq!(cascaded, ["a", "b", "c"]);
+        for key in ["a", "b", "c"] {
+            assert_eq!(manager.plugins[key].state, PluginState::Errored);
+        }
+        assert_eq!(manager.plugins["soft"].state, PluginState::Registered);
+    }
+
+    #[test]
+    fn load_order_puts_dependencies_first() {
+        let mut before = depending_on("Before", &[]);
+        before.load_before = vec!["zeta".to_string()];
+        let mut manager = manager_with(vec![
+            depending_on("Alpha", &["zeta"]),
+            before,
+            depending_on("Zeta", &[]),
+        ]);
+
+        assert_eq!(manager.compute_load_order(), ["before", "zeta", "alpha"]);
+        assert!(manager.dependency_report().is_empty());
+    }
+
+    #[test]
+    fn dependency_report() {
+        let mut soft = depending_on("Soft", &[]);
+        soft.soft_depends = vec!["a".to_string(), "nothere".to_string()];
+        let mut manager = manager_with(vec![
+            depending_on("A", &["b"]),
+            depending_on("B", &["a"]),
+            soft,
+            depending_on("D", &["missing"]),
+            depending_on("E", &["d"]),
+        ]);
+
+        // Plugins stuck behind a cycle still load, in name order
+        assert_eq!(manager.compute_load_order(), ["a", "b", "soft"]);
+        assert_eq!(manager.plugins["d"].state, PluginState::Errored);
+        assert_eq!(manager.plugins["e"].state, PluginState::Errored);
+
+        let report = manager.dependency_report();
+        assert_eq!(report.cycles, [["a", "b"]]);
+        assert_eq!(report.missing_dependencies.len(), 1);
+        assert_eq!(report.missing_dependencies[0].plugin, "D");
+        assert_eq!(report.missing_dependencies[0].missing, ["missing"]);
+        assert_eq!(report.skipped_soft_dependencies.len(), 1);
+        assert_eq!(report.skipped_soft_dependencies[0].dependency, "nothere");
+        assert_eq!(
+            report.skipped_soft_dependencies[0].reason,
+            SkipReason::NotInstalled
+        );
+    }
+
+    #[test]
+    fn duplicate_with_the_higher_version_wins() {
+        let mut manager = PluginManager::new();
+        manager
+            .add_plugin(test_plugin("Test", "1.0", "a/Test.jar"))
+            .unwrap();
+        manager
+            .add_plugin(test_plugin("test", "1.10", "b/Test.jar"))
+            .unwrap();
+        assert_eq!(manager.plugins["test"].path, PathBuf::from("b/Test.jar"));
+        assert_eq!(manager.skipped[0].path, PathBuf::from("a/Test.jar"));
+        assert_eq!(manager.skipped[0].state, PluginState::Skipped);
+
+        let mut manager = PluginManager::new();
+        manager
+            .add_plugin(test_plugin("test", "1.10", "b/Test.jar"))
+            .unwrap();
+        let duplicate = manager
+            .add_plugin(test_plugin("Test", "1.0", "a/Test.jar"))
+            .unwrap_err();
+        assert_eq!(duplicate.kept, PathBuf::from("b/Test.jar"));
+        assert_eq!(manager.plugins["test"].path, PathBuf::from("b/Test.jar"));
+    }
+
+    #[test]
+    fn duplicate_with_the_same_version_sorts_by_path() {
+        for paths in [["a/Test.jar", "b/Test.jar"], ["b/Test.jar", "a/Test.jar"]] {
+            let mut manager = PluginManager::new();
+            for path in paths {
+                let _ = manager.add_plugin(test_plugin("Test", "1.0", path));
+            }
+            assert_eq!(manager.plugins["test"].path, PathBuf::from("a/Test.jar"));
+            assert_eq!(manager.skipped.len(), 1);
+        }
+    }
+
+    #[test]
+    fn preferred_jar_wins_over_the_version() {
+        let config = PluginsConfig {
+            prefer_jars: HashMap::from([("Test".to_string(), "Test-old.jar".to_string())]),
+            ..Default::default()
+        };
+        for paths in [
+            ["Test-old.jar", "Test-new.jar"],
+            ["Test-new.jar", "Test-old.jar"],
+        ] {
+            let mut manager = PluginManager::new();
+            manager.configure(&config);
+            for path in paths {
+                let version = if path == "Test-old.jar" { "1.0" } else { "2.0" };
+                let _ = manager.add_plugin(test_plugin("Test", version, path));
+            }
+            assert_eq!(manager.plugins["test"].path, PathBuf::from("Test-old.jar"));
+        }
+    }
+}
@@ -1,3 +1,5 @@
 pub mod command_manager;
+pub mod dependency_report;
 pub mod event_manager;
 pub mod manager;
+pub mod permission_manager;
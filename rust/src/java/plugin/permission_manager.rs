@@ -0,0 +1,237 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use pumpkin::plugin::Context;
+use pumpkin_util::permission::Permission;
+
+use crate::{
+    config::spigot::{DefaultPermission, SpigotPluginYml},
+    java::plugin::manager::Plugin,
+};
+
+/// Pumpkin requires permission nodes to live in the registering plugin's namespace
+pub fn bukkit_permission_node(permission: &str) -> String {
+    format!("patchbukkit:{}", permission.trim().to_lowercase())
+}
+
+/// How many registrations hold each node PatchBukkit registered with Pumpkin.
+///
+/// Several plugins, or several commands of one plugin, can declare the same node. It stays
+/// registered until the last of them is unregistered.
+static PERMISSION_HOLDERS: LazyLock<Mutex<HashMap<String, usize>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Registers a permission, or takes another hold on a node PatchBukkit already registered.
+///
+/// Returns whether the caller holds the node and must release it with
+/// [`unregister_permission`]. The first registration decides the node's description and
+/// default.
+pub async fn register_permission(context: &Arc<Context>, permission: Permission) -> bool {
+    let node = permission.node.clone();
+    if let Some(holders) = PERMISSION_HOLDERS.lock().unwrap().get_mut(&node) {
+        *holders += 1;
+        return true;
+    }
+    match context.register_permission(permission).await {
+        Ok(()) => {
+            *PERMISSION_HOLDERS.lock().unwrap().entry(node).or_default() += 1;
+            true
+        }
+        Err(e) => {
+            log::debug!("Permission {} not registered: {}", node, e);
+            false
+        }
+    }
+}
+
+/// Releases a hold taken by [`register_permission`], removing the node from Pumpkin once
+/// nobody holds it anymore
+pub async fn unregister_permission(context: &Arc<Context>, node: &str) {
+    {
+        let mut holders = PERMISSION_HOLDERS.lock().unwrap();
+        match holders.get_mut(node) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                return;
+            }
+            Some(_) => {
+                holders.remove(node);
+            }
+            None => return,
+        }
+    }
+    if let Err(e) = context.unregister_permission(node).await {
+        log::debug!("Permission {} not unregistered: {}", node, e);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct PermissionNode {
+    description: String,
+    default: DefaultPermission,
+    children: HashMap<String, bool>,
+}
+
+/// Registers the `permissions:` tree of a plugin's `plugin.yml` with Pumpkin.
+///
+/// Returns the Pumpkin nodes that were registered.
+pub async fn register_plugin_permissions(context: &Arc<Context>, plugin: &Plugin) -> Vec<String> {
+    let Some(config) = plugin.spigot_config() else {
+        return Vec::new();
+    };
+    let nodes = permission_nodes(config);
+
+    let op_level = context.server.basic_config.op_permission_level;
+    let mut registered = Vec::new();
+    for (name, node) in nodes {
+        let pumpkin_node = bukkit_permission_node(&name);
+        node.default.warn_unsupported(&plugin.name, &name);
+        let mut permission = Permission::new(
+            &pumpkin_node,
+            &node.description,
+            node.default.to_permission_default(op_level),
+        );
+        for (child, value) in node.children {
+            permission
+                .children
+                .insert(bukkit_permission_node(&child), value);
+        }
+        if register_permission(context, permission).await {
+            registered.push(pumpkin_node);
+        }
+    }
+
+    log::debug!(
+        "Registered {} permission(s) for plugin {}",
+        registered.len(),
+        plugin.name
+    );
+    registered
+}
+
+/// The nodes of a `permissions:` tree, by lowercase name.
+///
+/// Children that are not declared on their own are added as well. They inherit the default
+/// of the parent granting them, or are denied by default when the parent negates them.
+fn permission_nodes(config: &SpigotPluginYml) -> HashMap<String, PermissionNode> {
+    let Some(declared) = &config.permissions else {
+        return HashMap::new();
+    };
+    let plugin_default = config.default_permission.clone().unwrap_or_default();
+
+    let mut nodes: HashMap<String, PermissionNode> = HashMap::new();
+    for (name, permission) in declared {
+        nodes.insert(
+            name.trim().to_lowercase(),
+            PermissionNode {
+                description: permission.description.clone().unwrap_or_default(),
+                default: permission
+                    .default
+                    .clone()
+                    .unwrap_or_else(|| plugin_default.clone()),
+                children: permission
+                    .children
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(child, value)| (child.trim().to_lowercase(), value))
+                    .collect(),
+            },
+        );
+    }
+
+    // In name order, so a child granted by several parents always gets the same default
+    let mut parents: Vec<String> = nodes.keys().cloned().collect();
+    parents.sort();
+    let mut queue = VecDeque::from(parents);
+    while let Some(parent) = queue.pop_front() {
+        let (parent_default, children) = match nodes.get(&parent) {
+            Some(node) => (node.default.clone(), node.children.clone()),
+            None => continue,
+        };
+        let mut children: Vec<(String, bool)> = children.into_iter().collect();
+        children.sort();
+        for (child, value) in children {
+            if nodes.contains_key(&child) {
+                continue;
+            }
+            nodes.insert(
+                child.clone(),
+                PermissionNode {
+                    description: format!("Granted by {}", parent),
+                    default: if value {
+                        parent_default.clone()
+                    } else {
+                        DefaultPermission::False
+                    },
+                    children: HashMap::new(),
+                },
+            );
+            queue.push_back(child);
+        }
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes(permissions: &str) -> HashMap<String, PermissionNode> {
+        let yaml = format!("name: Test\nversion: 1.0\nmain: a.B\n{}", permissions);
+        permission_nodes(&SpigotPluginYml::from_str(&yaml).unwrap())
+    }
+
+    #[test]
+    fn undeclared_children_inherit_the_parent_default() {
+        let nodes = nodes(
+            "permissions:\n  test.*:\n    default: true\n    children:\n      test.use: true\n      test.admin: false\n",
+        );
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes["test.use"].default, DefaultPermission::True);
+        assert_eq!(nodes["test.use"].description, "Granted by test.*");
+        assert_eq!(nodes["test.admin"].default, DefaultPermission::False);
+    }
+
+    #[test]
+    fn declared_children_keep_their_own_default() {
+        let nodes = nodes(
+            "permissions:\n  test.*:\n    default: true\n    children:\n      Test.Use: true\n  test.use:\n    description: Use it\n    default: op\n",
+        );
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes["test.use"].default, DefaultPermission::Op);
+        assert_eq!(nodes["test.use"].description, "Use it");
+        assert_eq!(
+            nodes["test.*"].children,
+            HashMap::from([("test.use".to_string(), true)])
+        );
+    }
+
+    #[test]
+    fn plugin_default_applies_to_nodes_without_one() {
+        let nodes = nodes(
+            "default-permission: notop\npermissions:\n  test.*:\n    children:\n      test.use: true\n",
+        );
+        assert_eq!(nodes["test.*"].default, DefaultPermission::NotOp);
+        assert_eq!(nodes["test.use"].default, DefaultPermission::NotOp);
+    }
+
+    #[test]
+    fn children_of_children_are_expanded() {
+        let nodes = nodes(
+            "permissions:\n  test.*:\n    default: op\n    children:\n      test.admin.*: true\n  test.admin.*:\n    default: false\n    children:\n      test.admin.reload: true\n",
+        );
+        assert_eq!(nodes["test.admin.reload"].default, DefaultPermission::False);
+        assert_eq!(
+            nodes["test.admin.reload"].description,
+            "Granted by test.admin.*"
+        );
+    }
+
+    #[test]
+    fn no_permissions() {
+        assert!(nodes("").is_empty());
+    }
+}
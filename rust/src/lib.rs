@@ -1,7 +1,8 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use pumpkin::plugin::Context;
 use pumpkin_api_macros::{plugin_impl, plugin_method};
+use pumpkin_util::permission::{Permission, PermissionDefault};
 
 pub mod commands;
 pub mod config;
@@ -9,21 +10,35 @@ pub mod directories;
 pub mod events;
 pub mod java;
 
+use commands::admin;
+use config::{patchbukkit::PatchBukkitConfig, spigot::LoadOrder};
 use directories::setup_directories;
 use java::{
-    jar::discover_jar_files,
+    jar::{apply_plugin_updates, discover_jar_files},
+    plugin::{dependency_report::DEPENDENCY_REPORT_FILE, permission_manager::register_permission},
     resources::{cleanup_stale_files, sync_embedded_resources},
 };
-use tokio::sync::{
-    mpsc::{self, Receiver},
-    oneshot,
+use tokio::{
+    sync::mpsc::{self, Receiver},
+    task::JoinHandle,
 };
 
 use crate::java::jvm::{
+    channel::{self, CommandKind, request},
     commands::{JvmCommand, LoadPluginResult},
     worker::JvmWorker,
 };
 
+/// What happened to a discovered JAR, for the summary printed at the end of startup
+enum JarOutcome {
+    Loaded(String),
+    Failed(String),
+    Skipped(String),
+}
+
+/// How often to check whether Pumpkin has loaded its worlds before enabling POSTWORLD plugins
+const WORLD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 async fn on_load_inner(plugin: &mut PatchBukkitPlugin, server: Arc<Context>) -> Result<(), String> {
     server.init_log();
     log::info!("Starting PatchBukkit");
@@ -31,68 +46,106 @@ async fn on_load_inner(plugin: &mut PatchBukkitPlugin, server: Arc<Context>) ->
     // Setup directories
     let dirs = setup_directories(&server)?;
 
+    // Load patchbukkit.yml, writing the defaults on first start
+    let config = Arc::new(PatchBukkitConfig::load(&dirs.base));
+    channel::configure(&config.channel);
+    plugin
+        .command_tx
+        .send(JvmCommand::Configure {
+            config: config.clone(),
+        })
+        .await
+        .map_err(|e| format!("Failed to send command to configure plugin loading: {}", e))?;
+
+    // Swap in plugin jars staged in the update folder
+    apply_plugin_updates(&dirs.plugins, &dirs.plugin_updates, &config.plugins);
+
     // Discover and prepare JAR files
-    let jar_paths = discover_jar_files(&dirs.plugins);
+    let jar_paths = discover_jar_files(&dirs.plugins, &config.plugins);
+    let mut load_summary = Vec::new();
     for jar_path in jar_paths {
-        {
-            let (tx, rx) = oneshot::channel();
-            let result = plugin
-                .command_tx
-                .send(JvmCommand::LoadPlugin {
-                    plugin_path: jar_path.clone(),
-                    respond_to: tx,
-                })
-                .await;
-            if let Err(e) = result {
+        let result = request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::LoadPlugin {
+                plugin_path: jar_path.clone(),
+                respond_to,
+            }
+        })
+        .await;
+        let file_name = jar_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| jar_path.display().to_string());
+        let outcome = match result {
+            Ok(result) => match result {
+                LoadPluginResult::SuccessfullyLoadedSpigot => {
+                    log::info!("Loaded Spigot plugin from JAR `{}`", jar_path.display());
+                    JarOutcome::Loaded("Spigot plugin".to_string())
+                }
+                LoadPluginResult::SuccessfullyLoadedPaper => {
+                    log::info!("Loaded Paper plugin from JAR `{}`", jar_path.display());
+                    JarOutcome::Loaded("Paper plugin".to_string())
+                }
+                LoadPluginResult::FailedToLoadSpigotPlugin(error) => {
+                    log::error!(
+                        "Failed to load Spigot plugin from JAR `{}` with error: {}",
+                        jar_path.display(),
+                        error
+                    );
+                    JarOutcome::Failed(error.to_string())
+                }
+                LoadPluginResult::FailedToLoadPaperPlugin(error) => {
+                    log::error!(
+                        "Failed to load Paper plugin from JAR `{}` with error: {}",
+                        jar_path.display(),
+                        error
+                    );
+                    JarOutcome::Failed(error.to_string())
+                }
+                LoadPluginResult::FailedToReadConfigurationFile(error) => {
+                    log::error!(
+                        "Failed to read configuration file from JAR `{}`: {}",
+                        jar_path.display(),
+                        error
+                    );
+                    JarOutcome::Failed(error.to_string())
+                }
+                LoadPluginResult::InvalidConfiguration(error) => {
+                    log::error!(
+                        "Invalid plugin description in JAR `{}`: {}",
+                        jar_path.display(),
+                        error
+                    );
+                    JarOutcome::Failed(error.to_string())
+                }
+                LoadPluginResult::NoConfigurationFile => {
+                    log::warn!(
+                        "No configuration file found for plugin from JAR `{}`",
+                        jar_path.display()
+                    );
+                    JarOutcome::Skipped("no plugin.yml or paper-plugin.yml".to_string())
+                }
+                LoadPluginResult::DuplicatePlugin(duplicate) => {
+                    JarOutcome::Skipped(duplicate.to_string())
+                }
+                LoadPluginResult::IncompatibleApiVersion(error) => {
+                    log::error!(
+                        "Refusing to load plugin from JAR `{}`: {}",
+                        jar_path.display(),
+                        error
+                    );
+                    JarOutcome::Failed(error.to_string())
+                }
+            },
+            Err(e) => {
                 log::error!(
-                    "Failed to send command to load plugin {}: {}",
+                    "Failed to load plugin from JAR `{}`: {}",
                     jar_path.display(),
                     e
                 );
-            }
-            match rx.await {
-                Ok(result) => match result {
-                    LoadPluginResult::SuccessfullyLoadedSpigot => {
-                        log::info!("Loaded Spigot plugin from JAR `{}`", jar_path.display())
-                    }
-                    LoadPluginResult::SuccessfullyLoadedPaper => {
-                        log::info!("Loaded Paper plugin from JAR `{}`", jar_path.display())
-                    }
-                    LoadPluginResult::FailedToLoadSpigotPlugin(error) => {
-                        log::error!(
-                            "Failed to load Spigot plugin from JAR `{}` with error: {}",
-                            jar_path.display(),
-                            error
-                        )
-                    }
-                    LoadPluginResult::FailedToLoadPaperPlugin(error) => {
-                        log::error!(
-                            "Failed to load Paper plugin from JAR `{}` with error: {}",
-                            jar_path.display(),
-                            error
-                        )
-                    }
-                    LoadPluginResult::FailedToReadConfigurationFile(error) => {
-                        log::error!(
-                            "Failed to read configuration file from JAR `{}`: {}",
-                            jar_path.display(),
-                            error
-                        )
-                    }
-                    LoadPluginResult::NoConfigurationFile => {
-                        log::warn!(
-                            "No configuration file found for plugin from JAR `{}`",
-                            jar_path.display()
-                        )
-                    }
-                },
-                Err(e) => log::error!(
-                    "Failed to receive load plugin response for JAR `{}`: {}",
-                    jar_path.display(),
-                    e
-                ),
+                JarOutcome::Failed(e.to_string())
             }
         };
+        load_summary.push((file_name, outcome));
     }
 
     // Manage embedded resources
@@ -100,79 +153,169 @@ async fn on_load_inner(plugin: &mut PatchBukkitPlugin, server: Arc<Context>) ->
     sync_embedded_resources(&dirs.j4rs)?;
 
     {
-        let (tx, rx) = oneshot::channel();
-        plugin
-            .command_tx
-            .send(JvmCommand::Initialize {
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::Initialize {
                 j4rs_path: dirs.j4rs,
-                respond_to: tx,
+                respond_to,
                 context: server.clone(),
                 command_tx: plugin.command_tx.clone(),
-            })
-            .await
-            .map_err(|e| format!("Failed to send command to initialize J4RS: {}", e))?;
-        rx.await
-            .map_err(|e| format!("Unable to receive response from J4RS initialization: {}", e))?
-            .map_err(|e| format!("Failed to initialize all plugins: {}", e))?;
+                config: config.clone(),
+            }
+        })
+        .await
+        .map_err(|e| format!("Unable to initialize J4RS: {}", e))?
+        .map_err(|e| format!("Failed to initialize all plugins: {}", e))?;
     }
 
     {
-        let (tx, rx) = oneshot::channel();
-        plugin
-            .command_tx
-            .send(JvmCommand::InstantiateAllPlugins {
-                respond_to: tx,
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::BootstrapAllPlugins { respond_to }
+        })
+        .await
+        .map_err(|e| format!("Unable to bootstrap plugins: {}", e))?
+        .map_err(|e| format!("Failed to bootstrap all plugins: {}", e))?;
+    }
+
+    {
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::InstantiateAllPlugins {
+                respond_to,
                 server: server.clone(),
                 command_tx: plugin.command_tx.clone(),
-            })
-            .await
-            .map_err(|e| format!("Failed to send command to instantiate plugins: {}", e))?;
-        rx.await
-            .map_err(|e| format!("Unable to receive response from instantiate plugins: {}", e))?
-            .map_err(|e| format!("Failed to instantiate all plugins: {}", e))?;
+            }
+        })
+        .await
+        .map_err(|e| format!("Unable to instantiate plugins: {}", e))?
+        .map_err(|e| format!("Failed to instantiate all plugins: {}", e))?;
+    }
+
+    {
+        let report = request(&plugin.command_tx, CommandKind::Query, |respond_to| {
+            JvmCommand::GetDependencyReport { respond_to }
+        })
+        .await
+        .map_err(|e| format!("Unable to get the dependency report: {}", e))?;
+        report.log();
+        if let Err(e) = report.write(&dirs.base) {
+            log::warn!("Failed to write {}: {}", DEPENDENCY_REPORT_FILE, e);
+        }
     }
 
     {
-        let (tx, rx) = oneshot::channel();
-        plugin
-            .command_tx
-            .send(JvmCommand::EnableAllPlugins { respond_to: tx })
-            .await
-            .map_err(|e| format!("Failed to send command to enable all plugins: {}", e))?;
-        rx.await
-            .map_err(|e| format!("Unable to receive response from enable all plugins: {}", e))?
-            .map_err(|e| format!("Failed to enable all plugins: {}", e))?;
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::EnableAllPlugins {
+                load: LoadOrder::Startup,
+                respond_to,
+            }
+        })
+        .await
+        .map_err(|e| format!("Unable to enable STARTUP plugins: {}", e))?
+        .map_err(|e| format!("Failed to enable STARTUP plugins: {}", e))?;
     };
 
+    plugin.postworld_task = Some(tokio::spawn(enable_postworld_plugins(
+        server.clone(),
+        plugin.command_tx.clone(),
+    )));
+
+    register_permission(
+        &server,
+        Permission::new(
+            admin::PERMISSION,
+            "Allows using /patchbukkit",
+            PermissionDefault::Op(server.server.basic_config.op_permission_level),
+        ),
+    )
+    .await;
+    server
+        .register_command(
+            admin::init_patchbukkit_command(plugin.command_tx.clone()),
+            admin::PERMISSION,
+        )
+        .await;
+
+    log_load_summary(&load_summary);
+
     Ok(())
 }
 
+/// Prints one row per discovered JAR with whether it was loaded, failed or skipped
+fn log_load_summary(summary: &[(String, JarOutcome)]) {
+    let count = |matches: fn(&JarOutcome) -> bool| {
+        summary
+            .iter()
+            .filter(|(_, outcome)| matches(outcome))
+            .count()
+    };
+    log::info!(
+        "PatchBukkit plugin summary: {} loaded, {} failed, {} skipped",
+        count(|outcome| matches!(outcome, JarOutcome::Loaded(_))),
+        count(|outcome| matches!(outcome, JarOutcome::Failed(_))),
+        count(|outcome| matches!(outcome, JarOutcome::Skipped(_))),
+    );
+
+    let width = summary
+        .iter()
+        .map(|(jar, _)| jar.len())
+        .max()
+        .unwrap_or(0)
+        .max("JAR".len());
+    log::info!("  {:<width$}  {:<7}  DETAIL", "JAR", "RESULT");
+    for (jar, outcome) in summary {
+        let (result, detail) = match outcome {
+            JarOutcome::Loaded(detail) => ("loaded", detail),
+            JarOutcome::Failed(detail) => ("failed", detail),
+            JarOutcome::Skipped(detail) => ("skipped", detail),
+        };
+        log::info!("  {:<width$}  {:<7}  {}", jar, result, detail);
+    }
+}
+
+/// Enables `load: POSTWORLD` plugins once Pumpkin has loaded its worlds
+async fn enable_postworld_plugins(server: Arc<Context>, command_tx: mpsc::Sender<JvmCommand>) {
+    while server.server.worlds.read().await.is_empty() {
+        tokio::time::sleep(WORLD_POLL_INTERVAL).await;
+    }
+
+    let result = request(&command_tx, CommandKind::Lifecycle, |respond_to| {
+        JvmCommand::EnableAllPlugins {
+            load: LoadOrder::Postworld,
+            respond_to,
+        }
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => log::error!("Failed to enable POSTWORLD plugins: {}", e),
+        Err(e) => log::error!("Unable to enable POSTWORLD plugins: {}", e),
+    }
+}
+
 async fn on_unload_inner(
     plugin: &mut PatchBukkitPlugin,
     _server: Arc<Context>,
 ) -> Result<(), String> {
+    // Still waiting for the worlds, the POSTWORLD plugins must not be enabled after this
+    if let Some(postworld_task) = plugin.postworld_task.take() {
+        postworld_task.abort();
+    }
+
     {
-        let (tx, rx) = oneshot::channel();
-        plugin
-            .command_tx
-            .send(JvmCommand::DisableAllPlugins { respond_to: tx })
-            .await
-            .map_err(|e| format!("Failed to send command to disable all plugins: {}", e))?;
-        rx.await
-            .map_err(|e| format!("Unable to receive response from disable all plugins: {}", e))?
-            .map_err(|e| format!("Failed to disable all plugins: {}", e))?;
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::DisableAllPlugins { respond_to }
+        })
+        .await
+        .map_err(|e| format!("Unable to disable all plugins: {}", e))?
+        .map_err(|e| format!("Failed to disable all plugins: {}", e))?;
     }
 
     {
-        let (tx, rx) = oneshot::channel();
-        plugin
-            .command_tx
-            .send(JvmCommand::Shutdown { respond_to: tx })
-            .await
-            .map_err(|e| format!("Failed to send command to shutdown: {}", e))?;
-        rx.await
-            .map_err(|e| format!("Unable to receive response from shutdown: {}", e))?
-            .map_err(|e| format!("Failed to shutdown: {}", e))?;
+        request(&plugin.command_tx, CommandKind::Lifecycle, |respond_to| {
+            JvmCommand::Shutdown { respond_to }
+        })
+        .await
+        .map_err(|e| format!("Unable to shut down the JVM: {}", e))?
+        .map_err(|e| format!("Failed to shutdown: {}", e))?;
     }
 
     Ok(())
@@ -191,6 +334,8 @@ async fn on_unload(&mut self, server: Arc<Context>) -> Result<(), String> {
 #[plugin_impl]
 pub struct PatchBukkitPlugin {
     pub command_tx: mpsc::Sender<JvmCommand>,
+    /// Waits for the worlds to enable the POSTWORLD plugins, aborted on unload
+    postworld_task: Option<JoinHandle<()>>,
 }
 
 impl PatchBukkitPlugin {
@@ -208,7 +353,10 @@ impl PatchBukkitPlugin {
                 jvm_thread_task(rx);
             })
             .unwrap();
-        PatchBukkitPlugin { command_tx: tx }
+        PatchBukkitPlugin {
+            command_tx: tx,
+            postworld_task: None,
+        }
     }
 }
 